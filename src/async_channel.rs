@@ -0,0 +1,270 @@
+//! Async channel with ring semantics. Requires `async` feature.
+//!
+//! [`RingSink`] waits for a free slot when the ring is full, or evicts the oldest item when it's
+//! created by [`lossy_channel`]. [`RingStream`] yields popped items and ends once the sink is
+//! closed and the ring is drained.
+//!
+//! ```
+//! extern crate futures;
+//! extern crate hoop;
+//!
+//! use futures::executor::block_on;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! let (mut sink, stream) = hoop::async_channel::lossy_channel(2);
+//! for i in 0..5 {
+//!     block_on(sink.send(i)).unwrap();
+//! }
+//! block_on(sink.close()).unwrap();
+//! assert_eq!(vec![3, 4], block_on(stream.collect::<Vec<_>>()));
+//! ```
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use sync::lock;
+use {Hoop, WriteResult};
+
+struct State<T> {
+    hoop: Hoop<T>,
+    lossy: bool,
+    sink_closed: bool,
+    stream_closed: bool,
+    // Stream waiting for an item.
+    item_waker: Option<Waker>,
+    // Sink waiting for a free slot.
+    space_waker: Option<Waker>,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+}
+
+fn wake(waker: &mut Option<Waker>) {
+    if let Some(waker) = waker.take() {
+        waker.wake();
+    }
+}
+
+fn with_mode<T>(capacity: usize, lossy: bool) -> (RingSink<T>, RingStream<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            hoop: Hoop::with_capacity(capacity),
+            lossy,
+            sink_closed: false,
+            stream_closed: false,
+            item_waker: None,
+            space_waker: None,
+        }),
+    });
+    (
+        RingSink {
+            shared: shared.clone(),
+        },
+        RingStream { shared },
+    )
+}
+
+/// Create a channel with desired capacity where sink waits for a free slot.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero, sink would wait for a free slot forever.
+pub fn channel<T>(capacity: usize) -> (RingSink<T>, RingStream<T>) {
+    assert!(
+        capacity > 0,
+        "channel that waits for a free slot needs non-zero capacity"
+    );
+    with_mode(capacity, false)
+}
+
+/// Create a channel with desired capacity where sink never waits and overwrites the oldest item
+/// instead.
+pub fn lossy_channel<T>(capacity: usize) -> (RingSink<T>, RingStream<T>) {
+    with_mode(capacity, true)
+}
+
+/// Writing half of an async channel.
+pub struct RingSink<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sink<T> for RingSink<T> {
+    type Error = Closed;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        let mut state = lock(&self.shared.state);
+        if state.stream_closed {
+            return Poll::Ready(Err(Closed));
+        }
+        if state.lossy || !state.hoop.is_full() {
+            return Poll::Ready(Ok(()));
+        }
+        state.space_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Closed> {
+        let mut state = lock(&self.shared.state);
+        if state.stream_closed {
+            return Err(Closed);
+        }
+        if state.lossy {
+            state.hoop.overwrite(item);
+        } else if state.hoop.write(item) == WriteResult::TooMany {
+            panic!("start_send called without poll_ready");
+        }
+        wake(&mut state.item_waker);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        // Items are visible to the stream as soon as they are sent.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        let mut state = lock(&self.shared.state);
+        state.sink_closed = true;
+        wake(&mut state.item_waker);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Drop for RingSink<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared.state);
+        state.sink_closed = true;
+        wake(&mut state.item_waker);
+    }
+}
+
+/// Reading half of an async channel.
+pub struct RingStream<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Stream for RingStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = lock(&self.shared.state);
+        if let Some(item) = state.hoop.pop() {
+            wake(&mut state.space_waker);
+            return Poll::Ready(Some(item));
+        }
+        if state.sink_closed {
+            return Poll::Ready(None);
+        }
+        state.item_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for RingStream<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared.state);
+        state.stream_closed = true;
+        wake(&mut state.space_waker);
+    }
+}
+
+/// Error returned by [`RingSink`] once its stream is dropped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Closed;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+    use futures::executor::{block_on, LocalPool};
+    use futures::stream;
+    use futures::task::LocalSpawnExt;
+    use futures::{FutureExt, SinkExt, StreamExt};
+
+    #[test]
+    fn sink_waits_for_space() {
+        let (sink, stream) = channel(2);
+        let mut pool = LocalPool::new();
+        let spawner = pool.spawner();
+        spawner
+            .spawn_local(
+                stream::iter((0..10).map(Ok))
+                    .forward(sink)
+                    .map(|r| r.unwrap()),
+            )
+            .unwrap();
+        let received = spawner
+            .spawn_local_with_handle(stream.collect::<Vec<_>>())
+            .unwrap();
+        assert_eq!((0..10).collect::<Vec<_>>(), pool.run_until(received));
+    }
+
+    #[test]
+    fn stream_waits_for_item() {
+        let (sink, stream) = channel(1);
+        let mut pool = LocalPool::new();
+        let spawner = pool.spawner();
+        let received = spawner
+            .spawn_local_with_handle(stream.collect::<Vec<char>>())
+            .unwrap();
+        pool.run_until_stalled();
+        spawner
+            .spawn_local(
+                stream::iter(vec![Ok('1'), Ok('2')])
+                    .forward(sink)
+                    .map(|r| r.unwrap()),
+            )
+            .unwrap();
+        assert_eq!(vec!['1', '2'], pool.run_until(received));
+    }
+
+    #[test]
+    fn lossy_sink_overwrites() {
+        let (mut sink, mut stream) = lossy_channel(2);
+        for c in "123".chars() {
+            block_on(sink.send(c)).unwrap();
+        }
+        assert_eq!(Some('2'), block_on(stream.next()));
+        drop(sink);
+        assert_eq!(Some('3'), block_on(stream.next()));
+        assert_eq!(None, block_on(stream.next()));
+    }
+
+    #[test]
+    #[should_panic(expected = "channel that waits for a free slot needs non-zero capacity")]
+    fn waiting_channel_without_capacity() {
+        channel::<u8>(0);
+    }
+
+    #[test]
+    fn lossy_channel_without_capacity_drops_everything() {
+        let (mut sink, stream) = lossy_channel(0);
+        block_on(sink.send(1)).unwrap();
+        drop(sink);
+        assert_eq!(Vec::<i32>::new(), block_on(stream.collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn sink_fails_without_stream() {
+        let (mut sink, stream) = channel(1);
+        drop(stream);
+        assert_eq!(Err(Closed), block_on(sink.send('1')));
+    }
+
+    #[test]
+    fn blocked_sink_fails_when_stream_dropped() {
+        let (sink, stream) = channel(1);
+        let mut pool = LocalPool::new();
+        let sent = pool
+            .spawner()
+            .spawn_local_with_handle(stream::iter(vec![Ok('1'), Ok('2')]).forward(sink))
+            .unwrap();
+        pool.run_until_stalled();
+        drop(stream);
+        assert_eq!(Err(Closed), pool.run_until(sent));
+    }
+}
@@ -0,0 +1,263 @@
+//! Byte ring that works as a bounded in-memory pipe.
+use std::io::{self, BufRead, Read, Write};
+use std::string::String;
+use std::vec::Vec;
+
+use core::mem::MaybeUninit;
+
+use {Hoop, Storage};
+
+/// Ring of bytes implementing [`Read`], [`Write`] and [`BufRead`]. Bytes are copied in and out
+/// in at most two chunks, one on each side of where storage wraps around.
+///
+/// Only byte-oriented methods are provided, [`into_inner`](#method.into_inner) gives back the
+/// underlying [`Hoop`] for everything else.
+///
+/// ```
+/// use hoop::HoopBytes;
+/// use std::io::{Read, Write};
+///
+/// let mut pipe = HoopBytes::with_capacity(8);
+/// assert_eq!(8, pipe.write(b"hello, world").unwrap());
+/// let mut out = [0; 5];
+/// pipe.read_exact(&mut out).unwrap();
+/// assert_eq!(b"hello", &out);
+/// assert_eq!(3, pipe.len());
+/// ```
+pub struct HoopBytes<S: Storage<u8> = Vec<MaybeUninit<u8>>> {
+    hoop: Hoop<u8, S>,
+}
+
+impl HoopBytes {
+    /// Create new byte ring with desired capacity.
+    pub fn with_capacity(capacity: usize) -> HoopBytes {
+        HoopBytes {
+            hoop: Hoop::with_capacity(capacity),
+        }
+    }
+}
+
+impl<S: Storage<u8>> HoopBytes<S> {
+    /// Unwrap underlying buffer.
+    pub fn into_inner(self) -> Hoop<u8, S> {
+        self.hoop
+    }
+
+    /// Number of bytes ring can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.hoop.capacity()
+    }
+
+    /// Number of bytes in a ring.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.hoop.len()
+    }
+
+    /// Whether ring holds no bytes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.hoop.is_empty()
+    }
+
+    /// Bytes as a pair of contiguous slices, from the oldest to the newest. Second slice is
+    /// empty unless bytes wrap around the end of storage.
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        self.hoop.as_slices()
+    }
+
+    /// Drop every byte.
+    pub fn clear(&mut self) {
+        self.hoop.clear()
+    }
+
+    /// Offset of the first `byte`, counting from the oldest one.
+    pub fn find(&self, byte: u8) -> Option<usize> {
+        let (head, tail) = self.hoop.as_slices();
+        match head.iter().position(|&b| b == byte) {
+            Some(offset) => Some(offset),
+            None => tail
+                .iter()
+                .position(|&b| b == byte)
+                .map(|offset| head.len() + offset),
+        }
+    }
+
+    /// Move bytes up to and including the first `delim` to `out`. Returns how many were moved,
+    /// or `None` without touching anything if there is no `delim` yet.
+    pub fn pop_until(&mut self, delim: u8, out: &mut Vec<u8>) -> Option<usize> {
+        let len = self.find(delim)? + 1;
+        let start = out.len();
+        out.resize(start + len, 0);
+        self.hoop.pop_into(&mut out[start..]);
+        Some(len)
+    }
+
+    /// Move a complete line, including its `\n`, to `out`. Returns how many bytes were moved,
+    /// or `None` without touching anything if there is no complete line yet.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if line is not valid UTF-8. Line is removed
+    /// from the ring anyway, and `out` is left as is.
+    ///
+    /// ```
+    /// use hoop::HoopBytes;
+    /// use std::io::Write;
+    ///
+    /// let mut pipe = HoopBytes::with_capacity(16);
+    /// pipe.write_all(b"one\ntw").unwrap();
+    /// let mut line = String::new();
+    /// assert_eq!(Some(4), pipe.pop_line(&mut line).unwrap());
+    /// assert_eq!("one\n", line);
+    /// assert_eq!(None, pipe.pop_line(&mut line).unwrap());
+    /// pipe.write_all(b"o\n").unwrap();
+    /// assert_eq!(Some(4), pipe.pop_line(&mut line).unwrap());
+    /// assert_eq!("one\ntwo\n", line);
+    /// ```
+    pub fn pop_line(&mut self, out: &mut String) -> io::Result<Option<usize>> {
+        let mut line = Vec::new();
+        let len = match self.pop_until(b'\n', &mut line) {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        match String::from_utf8(line) {
+            Ok(line) => {
+                out.push_str(&line);
+                Ok(Some(len))
+            }
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "line is not valid UTF-8",
+            )),
+        }
+    }
+}
+
+impl<S: Storage<u8>> From<Hoop<u8, S>> for HoopBytes<S> {
+    fn from(hoop: Hoop<u8, S>) -> HoopBytes<S> {
+        HoopBytes { hoop }
+    }
+}
+
+/// Reading from an empty ring returns `Ok(0)`, just like reading from an empty `VecDeque<u8>`.
+impl<S: Storage<u8>> Read for HoopBytes<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.hoop.pop_into(buf))
+    }
+}
+
+/// Writes are short once the ring is full, and write nothing at all at capacity, so
+/// [`write_all`](Write::write_all) fails with [`io::ErrorKind::WriteZero`] instead of
+/// overwriting.
+impl<S: Storage<u8>> Write for HoopBytes<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+        // Second round writes after wraparound.
+        for _ in 0..2 {
+            let rest = &buf[written..];
+            let mut grant = self.hoop.write_grant(rest.len());
+            let len = grant.copy_from_slice(rest);
+            if len == 0 {
+                break;
+            }
+            grant.commit(len);
+            written += len;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: Storage<u8>> BufRead for HoopBytes<S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.hoop.as_slices().0)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.hoop.read_grant().release(amt);
+    }
+}
+
+#[cfg(test)]
+#[allow(unused_must_use)]
+mod tests {
+    use super::*;
+    use {wrapped, ArrayHoop};
+
+    #[test]
+    fn short_write_when_full() {
+        let mut pipe = HoopBytes::from(wrapped(8, 5, b"abcdef".iter().cloned()));
+        assert_eq!((&b"abc"[..], &b"def"[..]), pipe.as_slices());
+        assert_eq!(2, pipe.write(b"ghij").unwrap());
+        assert_eq!(0, pipe.write(b"k").unwrap());
+        let err = pipe.write_all(b"k").unwrap_err();
+        assert_eq!(io::ErrorKind::WriteZero, err.kind());
+    }
+
+    #[test]
+    fn read_across_wraparound() {
+        let mut pipe = HoopBytes::from(wrapped(8, 5, b"abcdefg".iter().cloned()));
+        assert_eq!(1, pipe.write(b"hi").unwrap());
+        let mut out = [0; 5];
+        assert_eq!(5, pipe.read(&mut out).unwrap());
+        assert_eq!(b"abcde", &out);
+        let mut rest = Vec::new();
+        pipe.read_to_end(&mut rest).unwrap();
+        assert_eq!(b"fgh", &rest[..]);
+        assert_eq!(0, pipe.read(&mut out).unwrap());
+    }
+
+    #[test]
+    fn buf_read_walks_both_halves() {
+        let mut pipe = HoopBytes::from(wrapped(8, 5, b"ab\ncdef".iter().cloned()));
+        assert_eq!(b"ab\n", pipe.fill_buf().unwrap());
+        pipe.consume(1);
+        let mut line = String::new();
+        pipe.read_line(&mut line).unwrap();
+        assert_eq!("b\n", line);
+        assert_eq!(b"cdef", pipe.fill_buf().unwrap());
+        pipe.consume(4);
+        assert!(pipe.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pop_until_waits_for_delimiter() {
+        let mut pipe = HoopBytes::from(wrapped(8, 5, b"a,bc,d".iter().cloned()));
+        let mut out = Vec::new();
+        assert_eq!(Some(2), pipe.pop_until(b',', &mut out));
+        assert_eq!(Some(2), pipe.find(b','));
+        assert_eq!(Some(3), pipe.pop_until(b',', &mut out));
+        assert_eq!(None, pipe.pop_until(b',', &mut out));
+        assert_eq!(b"a,bc,", &out[..]);
+        assert_eq!(1, pipe.len());
+    }
+
+    #[test]
+    fn pop_line_rejects_invalid_utf8() {
+        let mut pipe = HoopBytes::from(wrapped(8, 5, b"\xff\nok\n".iter().cloned()));
+        let mut line = String::new();
+        let err = pipe.pop_line(&mut line).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert_eq!(Some(3), pipe.pop_line(&mut line).unwrap());
+        assert_eq!("ok\n", line);
+    }
+
+    #[test]
+    fn works_over_any_storage() {
+        let mut pipe = HoopBytes::from(ArrayHoop::<u8, 4>::new());
+        assert_eq!(4, pipe.capacity());
+        assert_eq!(4, pipe.write(b"12345").unwrap());
+        let mut out = Vec::new();
+        pipe.read_to_end(&mut out).unwrap();
+        assert_eq!(b"1234", &out[..]);
+        pipe.write_all(b"67").unwrap();
+        pipe.clear();
+        assert!(pipe.is_empty());
+        assert!(pipe.into_inner().is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+use core::iter::{DoubleEndedIterator, ExactSizeIterator, IntoIterator, Iterator};
+use core::ops::{Bound, RangeBounds};
+
+use {Hoop, Iter, IterMut, Storage};
+
+/// Owning iterator created by `into_iter` on a [`Hoop`].
+pub struct IntoIter<T, S: Storage<T>> {
+    hoop: Hoop<T, S>,
+}
+
+impl<T, S: Storage<T>> Iterator for IntoIter<T, S> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.hoop.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.hoop.len(), Some(self.hoop.len()))
+    }
+}
+
+impl<T, S: Storage<T>> DoubleEndedIterator for IntoIter<T, S> {
+    fn next_back(&mut self) -> Option<T> {
+        self.hoop.pop_back()
+    }
+}
+
+impl<T, S: Storage<T>> ExactSizeIterator for IntoIter<T, S> {}
+
+impl<T, S: Storage<T>> IntoIterator for Hoop<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T, S>;
+
+    /// Consume buffer, yielding items from the oldest to the newest.
+    fn into_iter(self) -> IntoIter<T, S> {
+        IntoIter { hoop: self }
+    }
+}
+
+impl<'data, T: 'data, S: Storage<T>> IntoIterator for &'data Hoop<T, S> {
+    type Item = &'data T;
+    type IntoIter = Iter<'data, T>;
+
+    fn into_iter(self) -> Iter<'data, T> {
+        self.iter()
+    }
+}
+
+impl<'data, T: 'data, S: Storage<T>> IntoIterator for &'data mut Hoop<T, S> {
+    type Item = &'data mut T;
+    type IntoIter = IterMut<'data, T>;
+
+    fn into_iter(self) -> IterMut<'data, T> {
+        self.iter_mut()
+    }
+}
+
+/// Iterator created by [`Hoop::drain`] and [`Hoop::drain_range`].
+///
+/// Items that weren't yielded are dropped along with the iterator and the remaining items are
+/// moved to close the gap.
+pub struct Drain<'data, T: 'data, S: 'data + Storage<T>> {
+    hoop: &'data mut Hoop<T, S>,
+    // Logical range being drained, relative to the oldest item.
+    start: usize,
+    end: usize,
+    // Items in `front..back` are not yielded yet.
+    front: usize,
+    back: usize,
+    // Length of a buffer before draining.
+    len: usize,
+}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> Drain<'data, T, S> {
+    pub(crate) fn new<R: RangeBounds<usize>>(hoop: &'data mut Hoop<T, S>, range: R) -> Self {
+        let len = hoop.len;
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1),
+            Bound::Excluded(&end) => Some(end),
+            Bound::Unbounded => Some(len),
+        };
+        // Bounds past `usize::MAX` are reported just like any other bound that is too big.
+        let past_max = usize::MAX as u128 + 1;
+        let end = end.unwrap_or_else(|| {
+            panic!("drain range end {} is out of bounds of {} items", past_max, len)
+        });
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1).unwrap_or_else(|| {
+                panic!("drain range starts at {} but ends at {}", past_max, end)
+            }),
+            Bound::Unbounded => 0,
+        };
+        assert!(start <= end, "drain range starts at {} but ends at {}", start, end);
+        assert!(end <= len, "drain range end {} is out of bounds of {} items", end, len);
+        // Until drain is dropped buffer owns only items before `start`, so leaking the drain
+        // leaks the rest of items instead of leaving buffer in a broken state.
+        hoop.len = start;
+        hoop.write_position = hoop.physical(start);
+        Drain {
+            hoop,
+            start,
+            end,
+            front: start,
+            back: end,
+            len,
+        }
+    }
+
+    // Move item `from` positions after the oldest one into a slot `to` positions after it.
+    fn relocate(&mut self, from: usize, to: usize) {
+        let from = self.hoop.physical(from);
+        let to = self.hoop.physical(to);
+        let slots = self.hoop.storage.slots_mut();
+        let item = unsafe { slots[from].assume_init_read() };
+        slots[to].write(item);
+    }
+}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> Iterator for Drain<'data, T, S> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        if self.front == self.back {
+            return None;
+        }
+        let idx = self.hoop.physical(self.front);
+        self.front += 1;
+        // Buffer doesn't own drained items anymore, so each one is read exactly once.
+        Some(unsafe { self.hoop.storage.slots_mut()[idx].assume_init_read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> DoubleEndedIterator for Drain<'data, T, S> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        let idx = self.hoop.physical(self.back);
+        Some(unsafe { self.hoop.storage.slots_mut()[idx].assume_init_read() })
+    }
+}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> ExactSizeIterator for Drain<'data, T, S> {}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> Drop for Drain<'data, T, S> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+        let drained = self.end - self.start;
+        let tail = self.len - self.end;
+        // Move whichever side of the gap is shorter.
+        if self.start <= tail {
+            for i in (0..self.start).rev() {
+                self.relocate(i, i + drained);
+            }
+            self.hoop.read_position = self.hoop.physical(drained);
+        } else {
+            for i in 0..tail {
+                self.relocate(self.end + i, self.start + i);
+            }
+        }
+        self.hoop.len = self.len - drained;
+        self.hoop.write_position = self.hoop.physical(self.hoop.len);
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+#[allow(unused_must_use)]
+mod tests {
+    use core::ops::Bound;
+    use std::rc::Rc;
+    use std::vec::Vec;
+    use {wrapped, Hoop};
+
+    #[test]
+    fn into_iter_both_ways() {
+        let mut iter = wrapped(4, 2, 0..4).into_iter();
+        assert_eq!(4, iter.len());
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(3), iter.next_back());
+        assert_eq!(Some(2), iter.next_back());
+        assert_eq!(Some(1), iter.next());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+    }
+
+    #[test]
+    fn into_iter_drops_leftovers() {
+        let token = Rc::new(());
+        let mut buffer = Hoop::with_capacity(3);
+        for _ in 0..3 {
+            buffer.write(token.clone());
+        }
+        let mut iter = buffer.into_iter();
+        iter.next();
+        drop(iter);
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn for_loops_over_references() {
+        let mut buffer = wrapped(3, 2, 0..3);
+        for item in &mut buffer {
+            *item *= 2;
+        }
+        let mut result = Vec::new();
+        for item in &buffer {
+            result.push(*item);
+        }
+        assert_eq!(vec![0, 2, 4], result);
+    }
+
+    #[test]
+    fn drain_everything() {
+        let mut buffer = wrapped(4, 2, 0..3);
+        let drained: Vec<usize> = buffer.drain().collect();
+        assert_eq!(vec![0, 1, 2], drained);
+        assert!(buffer.is_empty());
+        buffer.write(7);
+        assert_eq!(vec![7], buffer.iter().cloned().collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn drain_dropped_early_still_empties() {
+        let token = Rc::new(());
+        let mut buffer = Hoop::with_capacity(4);
+        for _ in 0..6 {
+            buffer.overwrite(token.clone());
+        }
+        {
+            let mut drain = buffer.drain();
+            drain.next();
+            drain.next_back();
+        }
+        assert!(buffer.is_empty());
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn drain_range_moves_shorter_side() {
+        for capacity in 5..8 {
+            for items in 0..capacity + 1 {
+                for start in 0..items + 1 {
+                    for end in start..items + 1 {
+                        let mut buffer = wrapped(capacity, 2, 0..items);
+                        let drained: Vec<usize> = buffer.drain_range(start..end).collect();
+                        assert_eq!((start..end).collect::<Vec<usize>>(), drained);
+                        let expected: Vec<usize> = (0..start).chain(end..items).collect();
+                        let forward: Vec<usize> = buffer.iter().cloned().collect();
+                        assert_eq!(expected, forward);
+                        if start < end {
+                            buffer.write(100);
+                            assert_eq!(Some(&100), buffer.get_back(0));
+                            assert_eq!(expected.len() + 1, buffer.len());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn drain_range_bounds() {
+        let mut buffer = wrapped(5, 2, 0..5);
+        assert_eq!(vec![1, 2], buffer.drain_range(1..=2).collect::<Vec<usize>>());
+        assert_eq!(vec![3, 4], buffer.drain_range(1..).collect::<Vec<usize>>());
+        assert_eq!(vec![0], buffer.drain_range(..1).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "drain range end 4 is out of bounds of 3 items")]
+    fn drain_range_out_of_bounds() {
+        let mut buffer = wrapped(5, 2, 0..3);
+        buffer.drain_range(1..4);
+    }
+
+    #[test]
+    #[should_panic(expected = "is out of bounds of 3 items")]
+    fn drain_range_end_past_usize_max() {
+        let mut buffer = wrapped(5, 2, 0..3);
+        buffer.drain_range(..=usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "but ends at 3")]
+    fn drain_range_start_past_usize_max() {
+        let mut buffer = wrapped(5, 2, 0..3);
+        buffer.drain_range((Bound::Excluded(usize::MAX), Bound::Unbounded));
+    }
+
+    #[test]
+    fn forgotten_drain_leaks_but_keeps_buffer_usable() {
+        let mut buffer = wrapped(4, 2, 0..4);
+        {
+            let mut drain = buffer.drain_range(1..3);
+            drain.next();
+            std::mem::forget(drain);
+        }
+        assert_eq!(vec![0], buffer.iter().cloned().collect::<Vec<usize>>());
+        buffer.write(9);
+        assert_eq!(vec![0, 9], buffer.iter().cloned().collect::<Vec<usize>>());
+    }
+}
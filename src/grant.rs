@@ -0,0 +1,373 @@
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+use core::slice;
+
+use {Hoop, Storage};
+
+/// Free slots right after the newest item, created by [`Hoop::write_grant`]. Nothing is
+/// visible in a buffer until it's committed.
+///
+/// Slots start out empty and are filled front to back through [`write`](#method.write) and
+/// [`copy_from_slice`](#method.copy_from_slice), or all at once through
+/// [`buf_init`](#method.buf_init) for `Default` items. Items that aren't committed are dropped
+/// along with the grant.
+pub struct WriteGrant<'data, T: 'data> {
+    slots: GrantedSlots<'data, T>,
+    // Borrowed from the buffer apart from its storage.
+    write_position: &'data mut usize,
+    buffer_len: &'data mut usize,
+    slot_count: usize,
+}
+
+impl<'data, T: 'data> WriteGrant<'data, T> {
+    pub(crate) fn new<S: Storage<T>>(hoop: &'data mut Hoop<T, S>, n: usize) -> Self {
+        if hoop.is_empty() {
+            // Nothing to keep in place, so start from the beginning to get the longest run.
+            hoop.read_position = 0;
+            hoop.write_position = 0;
+        }
+        let slot_count = hoop.slot_count();
+        let len = n
+            .min(hoop.free_slots())
+            .min(slot_count - hoop.write_position);
+        let Hoop {
+            storage,
+            write_position,
+            len: buffer_len,
+            ..
+        } = hoop;
+        let start = *write_position;
+        WriteGrant {
+            slots: GrantedSlots::new(&mut storage.slots_mut()[start..start + len]),
+            write_position,
+            buffer_len,
+            slot_count,
+        }
+    }
+
+    /// Number of granted slots.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether no slots were granted.
+    pub fn is_empty(&self) -> bool {
+        self.slots.len() == 0
+    }
+
+    /// Put `item` into `i`-th granted slot, dropping whatever was written there before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of the grant, or if any slot before it is empty.
+    pub fn write(&mut self, i: usize, item: T) {
+        self.slots.write(i, item)
+    }
+
+    /// Copy as many of `items` as fit into slots right after the filled ones. Returns number
+    /// of copied items.
+    pub fn copy_from_slice(&mut self, items: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        self.slots.copy_from_slice(items)
+    }
+
+    /// Granted slots with every one not yet filled set to `T::default()`.
+    pub fn buf_init(&mut self) -> &mut [T]
+    where
+        T: Default,
+    {
+        self.slots.buf_init()
+    }
+
+    /// Publish first `k` slots as the newest items in a buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer than `k` slots are filled.
+    pub fn commit(mut self, k: usize) {
+        self.slots.commit(k);
+        // Grant never wraps around, so at most it ends right at the last slot.
+        *self.write_position += k;
+        if *self.write_position == self.slot_count {
+            *self.write_position = 0;
+        }
+        *self.buffer_len += k;
+    }
+}
+
+// Granted slots that are filled front to back, shared by `WriteGrant` and
+// `spsc::WriteGrant`. They only differ in how committed items are published.
+pub(crate) struct GrantedSlots<'data, T: 'data> {
+    // Pointer rather than `&mut`, committed slots may be read by consumer while `commit` is
+    // still running. Slots are never handed out while any of them is empty, memory borrowed by
+    // `HoopRef::from_slice` must keep holding valid items.
+    first: *mut MaybeUninit<T>,
+    len: usize,
+    // Leading slots known to hold an item.
+    initialized: usize,
+    _slots: PhantomData<&'data mut [MaybeUninit<T>]>,
+}
+
+// Same as `&mut [T]`.
+unsafe impl<'data, T: Send> Send for GrantedSlots<'data, T> {}
+unsafe impl<'data, T: Sync> Sync for GrantedSlots<'data, T> {}
+
+impl<'data, T: 'data> GrantedSlots<'data, T> {
+    pub(crate) fn new(slots: &'data mut [MaybeUninit<T>]) -> Self {
+        GrantedSlots {
+            first: slots.as_mut_ptr(),
+            len: slots.len(),
+            initialized: 0,
+            _slots: PhantomData,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    fn slots(&mut self) -> &mut [MaybeUninit<T>] {
+        unsafe { slice::from_raw_parts_mut(self.first, self.len) }
+    }
+
+    pub(crate) fn write(&mut self, i: usize, item: T) {
+        assert!(i < self.len, "slot {} out of {} granted", i, self.len);
+        assert!(
+            i <= self.initialized,
+            "writing slot {}, but only {} before it are filled",
+            i,
+            self.initialized
+        );
+        let filled = i < self.initialized;
+        let slot = &mut self.slots()[i];
+        if filled {
+            let old = unsafe { slot.assume_init_read() };
+            slot.write(item);
+            drop(old);
+        } else {
+            slot.write(item);
+            self.initialized += 1;
+        }
+    }
+
+    pub(crate) fn copy_from_slice(&mut self, items: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let start = self.initialized;
+        let len = items.len().min(self.len - start);
+        let slots = &mut self.slots()[start..start + len];
+        unsafe { ptr::copy_nonoverlapping(items.as_ptr(), slots.as_mut_ptr() as *mut T, len) };
+        self.initialized += len;
+        len
+    }
+
+    pub(crate) fn buf_init(&mut self) -> &mut [T]
+    where
+        T: Default,
+    {
+        let (initialized, len) = (self.initialized, self.len);
+        for slot in &mut self.slots()[initialized..] {
+            slot.write(T::default());
+        }
+        self.initialized = len;
+        let slots = self.slots();
+        unsafe { slice::from_raw_parts_mut(slots.as_mut_ptr() as *mut T, len) }
+    }
+
+    // Leave first `k` slots to the buffer, which is now in charge of dropping them.
+    pub(crate) fn commit(&mut self, k: usize) {
+        assert!(
+            k <= self.initialized,
+            "committing {} slots, but only {} are filled",
+            k,
+            self.initialized
+        );
+        self.first = self.first.wrapping_add(k);
+        self.len -= k;
+        self.initialized -= k;
+    }
+}
+
+impl<'data, T: 'data> Drop for GrantedSlots<'data, T> {
+    fn drop(&mut self) {
+        let initialized = self.initialized;
+        let slots = &mut self.slots()[..initialized];
+        unsafe { ptr::drop_in_place(slots as *mut [MaybeUninit<T>] as *mut [T]) };
+    }
+}
+
+/// The oldest items that are contiguous in storage, created by [`Hoop::read_grant`].
+pub struct ReadGrant<'data, T: 'data, S: 'data + Storage<T>> {
+    hoop: &'data mut Hoop<T, S>,
+    len: usize,
+}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> ReadGrant<'data, T, S> {
+    pub(crate) fn new(hoop: &'data mut Hoop<T, S>) -> Self {
+        let len = hoop.as_slices().0.len();
+        ReadGrant { hoop, len }
+    }
+
+    /// Number of granted items.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no items were granted.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Granted items, from the oldest to the newest.
+    pub fn buf(&self) -> &[T] {
+        self.hoop.as_slices().0
+    }
+
+    /// Granted items that can be modified in place.
+    pub fn buf_mut(&mut self) -> &mut [T] {
+        self.hoop.as_mut_slices().0
+    }
+
+    /// Remove first `k` granted items from a buffer, dropping them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is more than granted.
+    pub fn release(self, k: usize) {
+        assert!(k <= self.len, "releasing {} items out of {} granted", k, self.len);
+        if mem::needs_drop::<T>() {
+            for _ in 0..k {
+                self.hoop.pop();
+            }
+        } else {
+            self.hoop.read_position = self.hoop.physical(k);
+            self.hoop.len -= k;
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(unused_must_use)]
+mod tests {
+    use std::rc::Rc;
+    use std::vec::Vec;
+    use {ArrayHoop, HoopRef};
+
+    #[test]
+    fn write_grant_is_contiguous() {
+        let mut buffer: ArrayHoop<u8, 4> = ArrayHoop::new();
+        buffer.write(1);
+        buffer.write(2);
+        buffer.write(3);
+        buffer.pop();
+        let mut grant = buffer.write_grant(4);
+        // Only the last slot is contiguous, first one is after wraparound.
+        assert_eq!(1, grant.len());
+        grant.buf_init()[0] = 4;
+        grant.commit(1);
+        let mut grant = buffer.write_grant(4);
+        assert_eq!(1, grant.len());
+        grant.write(0, 5);
+        grant.commit(1);
+        assert_eq!((&[2, 3, 4][..], &[5][..]), buffer.as_slices());
+        assert!(buffer.write_grant(1).is_empty());
+    }
+
+    #[test]
+    fn write_grant_on_empty_buffer_starts_over() {
+        let mut buffer: ArrayHoop<u8, 4> = ArrayHoop::new();
+        buffer.write(1);
+        buffer.write(2);
+        buffer.pop();
+        buffer.pop();
+        let mut grant = buffer.write_grant(10);
+        assert_eq!(4, grant.len());
+        grant.buf_init().copy_from_slice(&[1, 2, 3, 4]);
+        grant.commit(3);
+        assert_eq!((&[1, 2, 3][..], &[][..]), buffer.as_slices());
+    }
+
+    #[test]
+    fn uncommitted_items_are_dropped() {
+        let token = Rc::new(());
+        let mut buffer: ArrayHoop<Option<Rc<()>>, 4> = ArrayHoop::new();
+        {
+            let mut grant = buffer.write_grant(3);
+            for slot in grant.buf_init() {
+                *slot = Some(Rc::clone(&token));
+            }
+            assert_eq!(4, Rc::strong_count(&token));
+            grant.commit(1);
+        }
+        assert_eq!(2, Rc::strong_count(&token));
+        assert_eq!(1, buffer.len());
+        drop(buffer.write_grant(3));
+        assert_eq!(1, buffer.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "committing 2 slots, but only 1 are filled")]
+    fn commit_empty_slots() {
+        let mut buffer: ArrayHoop<u8, 4> = ArrayHoop::new();
+        let mut grant = buffer.write_grant(2);
+        grant.write(0, 1);
+        grant.commit(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "writing slot 2, but only 1 before it are filled")]
+    fn write_past_empty_slot() {
+        let mut buffer: ArrayHoop<u8, 4> = ArrayHoop::new();
+        let mut grant = buffer.write_grant(4);
+        grant.write(0, 1);
+        grant.write(2, 3);
+    }
+
+    #[test]
+    fn write_replaces_filled_slot() {
+        let token = Rc::new(());
+        let mut buffer: ArrayHoop<Rc<()>, 4> = ArrayHoop::new();
+        let mut grant = buffer.write_grant(2);
+        grant.write(0, Rc::clone(&token));
+        grant.write(0, Rc::clone(&token));
+        assert_eq!(2, Rc::strong_count(&token));
+        grant.commit(1);
+        assert_eq!(1, buffer.len());
+    }
+
+    #[test]
+    fn borrowed_slice_stays_initialized() {
+        let mut memory = [1u8, 2, 3, 4];
+        {
+            let mut buffer = HoopRef::from_slice(&mut memory);
+            buffer.write(5);
+            let mut grant = buffer.write_grant(4);
+            assert_eq!(2, grant.copy_from_slice(&[6, 7]));
+            grant.write(2, 8);
+            // Uncommitted items are left behind in memory, which is fine for `Copy` ones.
+            drop(grant);
+            buffer.pop();
+        }
+        assert_eq!([5, 6, 7, 8], memory);
+    }
+
+    #[test]
+    fn read_grant_releases_oldest() {
+        let mut buffer: ArrayHoop<u8, 4> = ArrayHoop::new();
+        for i in 0..6 {
+            buffer.overwrite(i);
+        }
+        let mut grant = buffer.read_grant();
+        assert_eq!(&[2, 3], grant.buf());
+        grant.buf_mut()[1] = 30;
+        grant.release(1);
+        assert_eq!(vec![30, 4, 5], buffer.iter().cloned().collect::<Vec<u8>>());
+        assert_eq!(&[30], buffer.read_grant().buf());
+        buffer.read_grant().release(1);
+        assert_eq!(&[4, 5], buffer.read_grant().buf());
+    }
+}
@@ -1,11 +1,134 @@
-use std::iter::{DoubleEndedIterator, Iterator};
+//! Fixed size ring buffer that can be iterated both ways without mutating it.
+//!
+//! The crate is `#![no_std]`. [`Hoop`] with heap storage needs `alloc` feature, [`ArrayHoop`]
+//! works everywhere. Features:
+//!
+//! - `std` (default): enables `alloc`, thread-safe `mpmc` ring and `HoopBytes` pipe.
+//! - `alloc`: heap-backed `Hoop::with_capacity` and lock-free `spsc` ring.
+//! - `async`: `async_channel` with `Sink` and `Stream` halves.
+//! - `mirror`: `mirror` storage that keeps items in one contiguous slice.
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "async")]
+extern crate futures_core;
+#[cfg(feature = "async")]
+extern crate futures_sink;
+#[cfg(all(feature = "mirror", target_os = "linux"))]
+extern crate libc;
+#[cfg(loom)]
+extern crate loom;
+#[cfg(all(test, feature = "std"))]
+extern crate proptest;
+#[cfg(any(feature = "std", test))]
+#[cfg_attr(test, macro_use)]
+extern crate std;
+#[cfg(all(test, feature = "async"))]
+extern crate futures;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::{DoubleEndedIterator, ExactSizeIterator, FusedIterator, Iterator};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ops::{Index, IndexMut, RangeBounds};
+use core::slice;
+
+#[cfg(feature = "async")]
+pub mod async_channel;
+#[cfg(feature = "std")]
+pub mod mpmc;
+#[cfg(feature = "alloc")]
+pub mod spsc;
+#[cfg(feature = "std")]
+mod bytes;
+mod drain;
+mod grant;
+#[cfg(feature = "mirror")]
+pub mod mirror;
+mod peek;
+mod record;
+mod ring_buffer;
+#[cfg(feature = "alloc")]
+mod sync;
+
+#[cfg(feature = "std")]
+pub use bytes::HoopBytes;
+pub use drain::{Drain, IntoIter};
+pub use grant::{ReadGrant, WriteGrant};
+pub use peek::PeekMut;
+pub use record::{ArrayRecordHoop, RecordError, RecordHoop, Records};
+pub use ring_buffer::RingBuffer;
+
+/// Memory a ring keeps its items in. Custom storage is put into a ring with
+/// [`Hoop::from_storage`].
+///
+/// # Safety
+///
+/// `slots` and `slots_mut` must always return the same memory, ring tracks which slots are
+/// initialized across calls. `capacity` must never change and never exceed number of slots.
+pub unsafe trait Storage<T> {
+    /// All slots, initialized or not.
+    fn slots(&self) -> &[MaybeUninit<T>];
+    /// All slots, initialized or not.
+    fn slots_mut(&mut self) -> &mut [MaybeUninit<T>];
+
+    /// Number of items a ring may hold at once. Ring still wraps around at the last slot, so
+    /// storage can have more slots than that, e.g. to match the page size.
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.slots().len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T> Storage<T> for Vec<MaybeUninit<T>> {
+    #[inline]
+    fn slots(&self) -> &[MaybeUninit<T>] {
+        self
+    }
+
+    #[inline]
+    fn slots_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        self
+    }
+}
+
+unsafe impl<T> Storage<T> for &mut [MaybeUninit<T>] {
+    #[inline]
+    fn slots(&self) -> &[MaybeUninit<T>] {
+        self
+    }
+
+    #[inline]
+    fn slots_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        self
+    }
+}
+
+unsafe impl<T, const N: usize> Storage<T> for [MaybeUninit<T>; N] {
+    #[inline]
+    fn slots(&self) -> &[MaybeUninit<T>] {
+        self
+    }
+
+    #[inline]
+    fn slots_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        self
+    }
+}
 
 /// Yet another ring buffer implmentation. This one has ability to iterate both ways without
 /// mutation buffer.
 ///
+/// Items live in `S`, which is a heap allocated `Vec` unless stated otherwise. Without `alloc`
+/// feature there is no default, see [`ArrayHoop`].
+///
 /// # Usage
 ///
 /// ```
+/// # #[cfg(feature = "alloc")] {
 /// use hoop::Hoop;
 ///
 /// let mut buffer = Hoop::with_capacity(4);
@@ -20,285 +143,1124 @@ use std::iter::{DoubleEndedIterator, Iterator};
 /// assert_eq!(Some(&'3'), iter.next_back());
 /// assert_eq!(None, iter.next());
 /// assert_eq!(None, iter.next_back());
+/// # }
 /// ```
-pub struct Hoop<T: Clone> {
-    inner: Vec<Option<T>>,
+pub struct Hoop<
+    T,
+    #[cfg(feature = "alloc")] S: Storage<T> = Vec<MaybeUninit<T>>,
+    #[cfg(not(feature = "alloc"))] S: Storage<T>,
+> {
+    // Slots in `read_position..read_position + len` (with wraparound) are initialized, the rest
+    // are not.
+    storage: S,
     // Next read
     read_position: usize,
     // Next Write
     write_position: usize,
+    // Number of initialized slots
+    len: usize,
+    _items: PhantomData<T>,
 }
 
-impl<T: Clone> Hoop<T> {
-    /// Create new ring buffer with desired capacity.
+/// [`Hoop`] that keeps its items inline, without allocating.
+///
+/// ```
+/// use hoop::ArrayHoop;
+///
+/// let mut buffer: ArrayHoop<char, 2> = ArrayHoop::new();
+/// buffer.write('1');
+/// buffer.write('2');
+/// buffer.overwrite('3');
+/// assert_eq!(Some('2'), buffer.pop());
+/// assert_eq!(Some(&'3'), buffer.iter().next());
+/// ```
+pub type ArrayHoop<T, const N: usize> = Hoop<T, [MaybeUninit<T>; N]>;
+
+#[cfg(feature = "alloc")]
+impl<T> Hoop<T> {
+    /// Create new ring buffer with desired capacity. Buffer with zero capacity is always full.
     pub fn with_capacity(capacity: usize) -> Hoop<T> {
+        let mut storage = Vec::with_capacity(capacity);
+        storage.resize_with(capacity, MaybeUninit::uninit);
+        Hoop::from_storage(storage)
+    }
+
+    /// Change capacity to `new_capacity`, keeping items in order. When shrinking below `len()`
+    /// the oldest items are dropped, so that only the newest `new_capacity` remain.
+    ///
+    /// ```
+    /// use hoop::Hoop;
+    ///
+    /// let mut buffer = Hoop::with_capacity(3);
+    /// for i in 0..5 {
+    ///     buffer.overwrite(i);
+    /// }
+    /// buffer.resize(5);
+    /// buffer.write(5);
+    /// assert_eq!(vec![2, 3, 4, 5], buffer.iter().cloned().collect::<Vec<_>>());
+    /// assert_eq!(vec![2, 3], buffer.shrink_to(2));
+    /// assert_eq!(vec![4, 5], buffer.iter().cloned().collect::<Vec<_>>());
+    /// ```
+    pub fn resize(&mut self, new_capacity: usize) {
+        while self.len > new_capacity {
+            self.pop();
+        }
+        self.set_capacity(new_capacity);
+    }
+
+    /// Shrink capacity to `new_capacity`, returning the oldest items that no longer fit. Does
+    /// nothing if capacity is already smaller.
+    pub fn shrink_to(&mut self, new_capacity: usize) -> Vec<T> {
+        let mut evicted = Vec::new();
+        if new_capacity >= self.capacity() {
+            return evicted;
+        }
+        let excess = self.len.saturating_sub(new_capacity);
+        evicted.extend(self.pop_n(excess));
+        self.set_capacity(new_capacity);
+        evicted
+    }
+
+    /// Increase capacity by exactly `additional` slots, keeping every item.
+    pub fn grow(&mut self, additional: usize) {
+        let new_capacity = self
+            .capacity()
+            .checked_add(additional)
+            .expect("capacity overflow");
+        self.set_capacity(new_capacity);
+    }
+
+    /// Make sure at least `additional` more items can be written without evicting anything.
+    /// Capacity is at least doubled when it has to grow, so repeated calls are amortized `O(1)`.
+    pub fn reserve(&mut self, additional: usize) {
+        if self.free_slots() >= additional {
+            return;
+        }
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        self.set_capacity(required.max(self.capacity().saturating_mul(2)));
+    }
+
+    /// Make sure at least `additional` more items can be written without evicting anything,
+    /// growing by no more than needed.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if self.free_slots() >= additional {
+            return;
+        }
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        self.set_capacity(required);
+    }
+
+    // Move items to the start of storage and reallocate it. Items must fit into `new_capacity`.
+    fn set_capacity(&mut self, new_capacity: usize) {
+        let read_position = self.read_position;
+        self.storage.rotate_left(read_position);
+        self.read_position = 0;
+        if new_capacity < self.storage.len() {
+            // Every item is below `len`, so only uninitialized slots are cut off.
+            self.storage.truncate(new_capacity);
+            self.storage.shrink_to_fit();
+        } else {
+            self.storage.resize_with(new_capacity, MaybeUninit::uninit);
+        }
+        self.write_position = self.physical(self.len);
+    }
+}
+
+impl<T, const N: usize> Hoop<T, [MaybeUninit<T>; N]> {
+    /// Create new ring buffer with capacity of `N`.
+    pub const fn new() -> Self {
+        Hoop::from_storage([const { MaybeUninit::uninit() }; N])
+    }
+}
+
+impl<T, const N: usize> Default for Hoop<T, [MaybeUninit<T>; N]> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// [`Hoop`] in memory borrowed from the caller, e.g. an arena or a DMA region. Never allocates.
+///
+/// ```
+/// use hoop::HoopRef;
+/// use std::mem::MaybeUninit;
+///
+/// let mut memory = [MaybeUninit::uninit(); 2];
+/// let mut buffer = HoopRef::from_uninit(&mut memory);
+/// buffer.write('1');
+/// buffer.write('2');
+/// buffer.overwrite('3');
+/// assert_eq!(Some('2'), buffer.pop());
+/// ```
+pub type HoopRef<'a, T> = Hoop<T, &'a mut [MaybeUninit<T>]>;
+
+impl<'a, T> Hoop<T, &'a mut [MaybeUninit<T>]> {
+    /// Create new ring buffer in `slots`. Capacity is `slots.len()`.
+    pub fn from_uninit(slots: &'a mut [MaybeUninit<T>]) -> Self {
+        Hoop::from_storage(slots)
+    }
+}
+
+impl<'a, T: Copy> Hoop<T, &'a mut [MaybeUninit<T>]> {
+    /// Create new ring buffer in `slots`, treating whatever they hold as garbage. Capacity is
+    /// `slots.len()`.
+    ///
+    /// Once buffer is gone every slot still holds some `T`: either the old value or one that was
+    /// written to the buffer.
+    pub fn from_slice(slots: &'a mut [T]) -> Self {
+        // Buffer only ever stores initialized items into a slot, so the slice stays valid `[T]`,
+        // and `T: Copy` makes reading an item out without clearing the slot fine.
+        let slots = unsafe { &mut *(slots as *mut [T] as *mut [MaybeUninit<T>]) };
+        Hoop::from_storage(slots)
+    }
+}
+
+impl<T, S: Storage<T>> Hoop<T, S> {
+    /// Create empty ring buffer that keeps its items in `storage`. Whatever `storage` held
+    /// before is treated as uninitialized, so it's never read or dropped.
+    ///
+    /// ```
+    /// use hoop::{Hoop, Storage};
+    /// use std::mem::MaybeUninit;
+    ///
+    /// struct Slots([MaybeUninit<u32>; 3]);
+    ///
+    /// unsafe impl Storage<u32> for Slots {
+    ///     fn slots(&self) -> &[MaybeUninit<u32>] {
+    ///         &self.0
+    ///     }
+    ///
+    ///     fn slots_mut(&mut self) -> &mut [MaybeUninit<u32>] {
+    ///         &mut self.0
+    ///     }
+    /// }
+    ///
+    /// let mut buffer = Hoop::from_storage(Slots([MaybeUninit::uninit(); 3]));
+    /// buffer.write(1);
+    /// assert_eq!(3, buffer.capacity());
+    /// assert_eq!(Some(1), buffer.pop());
+    /// ```
+    pub const fn from_storage(storage: S) -> Hoop<T, S> {
         Hoop {
-            inner: vec![None; capacity],
+            storage,
             read_position: 0,
             write_position: 0,
+            len: 0,
+            _items: PhantomData,
         }
     }
 
-    /// Capacity of inner Vec.
+    /// Number of items buffer can hold.
     #[inline]
     pub fn capacity(&self) -> usize {
-        self.inner.capacity()
+        self.storage.capacity()
+    }
+
+    /// Number of items in a buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether buffer holds no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether buffer is at capacity, so next `write` would fail.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    /// Number of items that can be written before buffer is full.
+    #[inline]
+    pub fn free_slots(&self) -> usize {
+        self.capacity() - self.len
     }
 
     /// Pop oldest item from a buffer.
     pub fn pop(&mut self) -> Option<T> {
-        let ret: Option<T> = self.inner[self.read_position].take();
-        if ret.is_some() {
-            self.read_position = self.advance(self.read_position);
+        if self.len == 0 {
+            return None;
+        }
+        let idx = self.read_position;
+        // Slot is initialized and moving `read_position` past it marks it as uninitialized again.
+        let ret = unsafe { self.storage.slots_mut()[idx].assume_init_read() };
+        self.read_position = self.advance(self.read_position);
+        self.len -= 1;
+        Some(ret)
+    }
+
+    /// Pop newest item from a buffer.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<char, 3> = ArrayHoop::new();
+    /// buffer.write('2');
+    /// buffer.write('3');
+    /// buffer.push_front('1');
+    /// assert_eq!(Some('3'), buffer.pop_back());
+    /// buffer.overwrite_front('0');
+    /// buffer.overwrite_front('a');
+    /// assert_eq!(vec!['a', '0', '1'], buffer.iter().cloned().collect::<Vec<_>>());
+    /// ```
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
         }
-        ret
+        self.write_position = self.retreat(self.write_position);
+        self.len -= 1;
+        // Slot is initialized and moving `write_position` back onto it marks it as
+        // uninitialized again.
+        Some(unsafe { self.storage.slots_mut()[self.write_position].assume_init_read() })
     }
 
     /// Try writting to a buffer.
     pub fn write(&mut self, item: T) -> WriteResult {
-        let idx = self.write_position;
-        {
-            let stored = &self.inner[idx];
-            if stored.is_some() {
-                return WriteResult::TooMany;
-            }
+        match self.try_write(item) {
+            Ok(()) => WriteResult::Done,
+            Err(_) => WriteResult::TooMany,
+        }
+    }
+
+    /// Try writting to a buffer, handing `item` back if it's full.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<char, 1> = ArrayHoop::new();
+    /// assert_eq!(Ok(()), buffer.try_write('1'));
+    /// assert_eq!(Err('2'), buffer.try_write('2'));
+    /// assert_eq!(Some('1'), buffer.push_overwrite('3'));
+    /// ```
+    pub fn try_write(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
         }
-        self.inner[idx] = Some(item);
+        let idx = self.write_position;
+        self.storage.slots_mut()[idx].write(item);
         self.write_position = self.advance(self.write_position);
-        WriteResult::Done
+        self.len += 1;
+        Ok(())
     }
 
     /// Write even if at a capacity. This ither is a normal write or overwrite + move read position
     /// forward.
+    ///
+    /// Buffer with zero capacity is always full and has no oldest item to make room with, so
+    /// `item` is dropped.
     pub fn overwrite(&mut self, item: T) {
-        let idx = self.write_position;
-        {
-            let stored = &self.inner[idx];
-            if stored.is_some() {
-                self.read_position = self.advance(self.read_position);
-            }
+        // Evicted item is dropped only once buffer is consistent again, in case destructor
+        // panics.
+        drop(self.push_overwrite(item));
+    }
+
+    /// Write even if at a capacity, returning the oldest item if it was evicted to make room.
+    ///
+    /// Buffer with zero capacity is always full, so `item` itself is returned.
+    pub fn push_overwrite(&mut self, item: T) -> Option<T> {
+        if self.capacity() == 0 {
+            return Some(item);
         }
-        self.inner[idx] = Some(item);
-        self.write_position = self.advance(self.write_position);
+        let evicted = if self.is_full() {
+            let oldest = self.read_position;
+            self.read_position = self.advance(oldest);
+            self.len -= 1;
+            // Storage may have more slots than capacity, so the oldest item isn't necessarily
+            // under `write_position`.
+            Some(unsafe { self.storage.slots_mut()[oldest].assume_init_read() })
+        } else {
+            None
+        };
+        let idx = self.write_position;
+        self.storage.slots_mut()[idx].write(item);
+        self.write_position = self.advance(idx);
+        self.len += 1;
+        evicted
     }
 
     /// Clear buffer. This is `O(n)` operation.
     pub fn clear(&mut self) {
+        while self.pop().is_some() {}
         self.read_position = 0;
         self.write_position = 0;
-		for el in &mut self.inner {
-			*el = None;
-		}
     }
 
-    /// Create non-consuming iterator.
-    pub fn iter(&self) -> Iter<T> {
-        Iter::new(self)
+    /// Try writting to a buffer before the oldest item.
+    pub fn push_front(&mut self, item: T) -> WriteResult {
+        match self.try_push_front(item) {
+            Ok(()) => WriteResult::Done,
+            Err(_) => WriteResult::TooMany,
+        }
     }
 
-    fn advance(&self, current: usize) -> usize {
-       if (current + 1) == self.capacity() {
-            0
-        } else {
-            current + 1
+    /// Try writting to a buffer before the oldest item, handing `item` back if it's full.
+    pub fn try_push_front(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
         }
+        self.read_position = self.retreat(self.read_position);
+        let idx = self.read_position;
+        self.storage.slots_mut()[idx].write(item);
+        self.len += 1;
+        Ok(())
     }
 
-    fn retreat(&self, current: usize) -> usize {
-        if current == 0 {
-            self.capacity() - 1
-        } else {
-            current - 1
+    /// Write before the oldest item even if at a capacity, evicting the newest item.
+    ///
+    /// Just like with [`Hoop::overwrite`], buffer with zero capacity drops `item`.
+    pub fn overwrite_front(&mut self, item: T) {
+        if self.capacity() == 0 {
+            return;
         }
+        let evicted = if self.is_full() { self.pop_back() } else { None };
+        if self.try_push_front(item).is_err() {
+            unreachable!("buffer is full after making room");
+        }
+        // Drop only once buffer is consistent again, in case destructor panics.
+        drop(evicted);
     }
-}
-
-pub struct Iter<'data, T: 'data + Clone> {
-    hoop: &'data Hoop<T>,
-    forward_position: usize,
-    seeking_forward: bool,
-    backward_position: usize,
-    seeking_backward: bool,
-}
 
-impl<'data, T: 'data + Clone> Iterator for Iter<'data, T> {
-    type Item = &'data T;
-    fn next(&mut self) -> Option<&'data T> {
-        // We looped back to the start.
-        if self.seeking_forward && self.forward_position == self.hoop.read_position {
-            return None;
+    /// Write as many items from `items` as there are free slots, in order. Returns how many
+    /// were written.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 4> = ArrayHoop::new();
+    /// assert_eq!(3, buffer.extend_from_slice(&[1, 2, 3]));
+    /// assert_eq!(1, buffer.extend_from_slice(&[4, 5]));
+    /// buffer.overwrite_from_slice(&[6, 7, 8, 9, 10]);
+    /// assert_eq!((&[7, 8, 9, 10][..], &[][..]), buffer.as_slices());
+    /// ```
+    pub fn extend_from_slice(&mut self, items: &[T]) -> usize
+    where
+        T: Clone,
+    {
+        let accepted = items.len().min(self.free_slots());
+        for item in &items[..accepted] {
+            let idx = self.write_position;
+            self.storage.slots_mut()[idx].write(item.clone());
+            self.write_position = self.advance(self.write_position);
+            self.len += 1;
         }
-        // We reached backward_position. We allowed to look what's underneather it.
-        if self.seeking_forward && self.forward_position > self.backward_position {
-            return None;
+        accepted
+    }
+
+    /// Write every item from `items`, evicting the oldest ones when at a capacity. Only the
+    /// newest items that fit are written at all.
+    pub fn overwrite_from_slice(&mut self, items: &[T])
+    where
+        T: Clone,
+    {
+        let newest = items.len().min(self.capacity());
+        for item in &items[items.len() - newest..] {
+            self.overwrite(item.clone());
         }
-        if let Some(ref item) = self.hoop.inner[self.forward_position] {
-            self.forward_position = self.hoop.advance(self.forward_position);
-            self.seeking_forward = true;
-            Some(item)
-        } else {
-            None
+    }
+
+    /// Pop the oldest items into `out`, as many as fit. Items are copied in at most two chunks.
+    /// Returns how many were popped.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 4> = ArrayHoop::new();
+    /// buffer.extend_from_slice(&[1, 2, 3]);
+    /// let mut out = [0; 2];
+    /// assert_eq!(2, buffer.pop_into(&mut out));
+    /// assert_eq!([1, 2], out);
+    /// assert_eq!(vec![3], buffer.pop_n(5).collect::<Vec<_>>());
+    /// ```
+    pub fn pop_into(&mut self, out: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let popped = out.len().min(self.len);
+        {
+            let (head, tail) = self.as_slices();
+            let from_head = popped.min(head.len());
+            out[..from_head].copy_from_slice(&head[..from_head]);
+            out[from_head..popped].copy_from_slice(&tail[..popped - from_head]);
         }
+        // `Copy` items have no destructor, so leaving them behind in storage is the same as
+        // moving them out.
+        self.read_position = self.physical(popped);
+        self.len -= popped;
+        popped
     }
-}
 
-impl <'data, T: 'data + Clone> DoubleEndedIterator for Iter<'data, T> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        // We looped back to the start.
-        if self.seeking_backward && self.backward_position == self.hoop.write_position {
+    /// Remove up to `n` oldest items, yielding them in order. Works like [`Hoop::drain_range`],
+    /// so items that weren't yielded are still removed.
+    pub fn pop_n(&mut self, n: usize) -> Drain<'_, T, S> {
+        let n = n.min(self.len);
+        self.drain_range(..n)
+    }
+
+    /// Item `index` positions after the oldest one, so `get(0)` is the oldest item.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 3> = ArrayHoop::new();
+    /// for i in 0..5 {
+    ///     buffer.overwrite(i);
+    /// }
+    /// assert_eq!(Some(&2), buffer.get(0));
+    /// assert_eq!(Some(&4), buffer.get(2));
+    /// assert_eq!(None, buffer.get(3));
+    /// assert_eq!(Some(&4), buffer.get_back(0));
+    /// assert_eq!(3, buffer[1]);
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
             return None;
         }
-        let ahead_of_reader = self.backward_position > self.hoop.read_position;
-        if self.seeking_backward && ahead_of_reader && self.backward_position < self.forward_position {
+        let idx = self.physical(index);
+        Some(unsafe { self.storage.slots()[idx].assume_init_ref() })
+    }
+
+    /// Mutable reference to item `index` positions after the oldest one.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
             return None;
         }
+        let idx = self.physical(index);
+        Some(unsafe { self.storage.slots_mut()[idx].assume_init_mut() })
+    }
 
-        if let Some(ref item) = self.hoop.inner[self.backward_position] {
-            self.backward_position = self.hoop.retreat(self.backward_position);
-            self.seeking_backward = true;
-            Some(item)
-        } else {
-            None
+    /// Item `index` positions before the newest one, so `get_back(0)` is the newest item.
+    pub fn get_back(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
         }
+        self.get(self.len - 1 - index)
     }
-}
 
-impl<'data, T: 'data + Clone> Iter<'data, T> {
-    fn new(hoop: &'data Hoop<T>) -> Self {
-        Iter {
-            hoop: hoop,
-            forward_position: hoop.read_position,
-            backward_position: hoop.retreat(hoop.write_position),
-            seeking_forward: false,
-            seeking_backward: false,
+    /// Mutable reference to item `index` positions before the newest one.
+    pub fn get_back_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
         }
+        let index = self.len - 1 - index;
+        self.get_mut(index)
     }
-}
 
+    /// The oldest item, same as `get(0)`.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 3> = ArrayHoop::new();
+    /// for i in 0..5 {
+    ///     buffer.overwrite(i);
+    /// }
+    /// assert_eq!(Some(&2), buffer.front());
+    /// assert_eq!(Some(&4), buffer.back());
+    /// if let Some(newest) = buffer.back_mut() {
+    ///     *newest = 40;
+    /// }
+    /// assert_eq!(Some(&40), buffer.back());
+    /// ```
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
 
-#[must_use]
-/// Result of a write operation.
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub enum WriteResult {
-    /// Item was written to a buffer.
-    Done,
-    /// Buffer can't take any more items.
-    TooMany,
-}
+    /// Mutable reference to the oldest item.
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
 
-#[cfg(test)]
-#[allow(unused_must_use)]
-mod tests {
-    use super::*;
+    /// The newest item, same as `get_back(0)`.
+    pub fn back(&self) -> Option<&T> {
+        self.get_back(0)
+    }
 
-    #[test]
-    fn error_on_read_empty_buffer() {
-        let mut buffer = Hoop::<char>::with_capacity(1);
-        assert_eq!(None, buffer.pop());
+    /// Mutable reference to the newest item.
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.get_back_mut(0)
     }
 
-    #[test]
-    fn write_and_read_back_item() {
-        let mut buffer = Hoop::with_capacity(1);
-        buffer.write('1');
-        assert_eq!(Some('1'), buffer.pop());
-        assert_eq!(None, buffer.pop());
+    /// Guard with mutable access to the oldest item that can also pop it, or `None` if buffer
+    /// is empty.
+    ///
+    /// ```
+    /// use hoop::{ArrayHoop, PeekMut};
+    ///
+    /// let mut buffer: ArrayHoop<i32, 3> = ArrayHoop::new();
+    /// buffer.write(1);
+    /// buffer.write(2);
+    /// if let Some(oldest) = buffer.peek_mut() {
+    ///     if *oldest == 1 {
+    ///         PeekMut::pop(oldest);
+    ///     }
+    /// }
+    /// assert_eq!(Some(&2), buffer.front());
+    /// ```
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, S>> {
+        PeekMut::new(self)
     }
 
-    #[test]
-    fn write_and_read_back_multiple_items() {
-        let mut buffer = Hoop::with_capacity(2);
-        buffer.write('1');
-        buffer.write('2');
-        assert_eq!(Some('1'), buffer.pop());
-        assert_eq!(Some('2'), buffer.pop());
-        assert_eq!(None, buffer.pop());
+    /// Create non-consuming iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.storage.slots(), self.read_position, self.len)
     }
 
-    #[test]
-    fn alternate_write_and_read() {
-        let mut buffer = Hoop::with_capacity(2);
-        buffer.write('1');
-        assert_eq!(Some('1'), buffer.pop());
-        buffer.write('2');
-        assert_eq!(Some('2'), buffer.pop());
+    /// Non-consuming iterator over the newest `n` items, or every item if there are fewer than
+    /// `n`. Items are still yielded from the oldest to the newest.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 4> = ArrayHoop::new();
+    /// for i in 0..6 {
+    ///     buffer.overwrite(i);
+    /// }
+    /// let window = buffer.last_n(3);
+    /// assert_eq!(3, window.len());
+    /// assert_eq!((&[3][..], &[4, 5][..]), window.as_slices());
+    /// assert_eq!(vec![3, 4, 5], window.cloned().collect::<Vec<_>>());
+    /// ```
+    pub fn last_n(&self, n: usize) -> Iter<'_, T> {
+        let n = n.min(self.len);
+        Iter::new(self.storage.slots(), self.physical(self.len - n), n)
     }
 
-    #[test]
-    fn clear_buffer() {
-        let mut buffer = Hoop::with_capacity(3);
-        buffer.write('1');
-        buffer.write('2');
-        buffer.write('3');
-        buffer.clear();
-        assert_eq!(None, buffer.pop());
-        buffer.write('1');
-        buffer.write('2');
-        assert_eq!(Some('1'), buffer.pop());
-        buffer.write('3');
-        assert_eq!(Some('2'), buffer.pop());
+    /// Non-consuming iterator over the oldest `n` items, or every item if there are fewer than
+    /// `n`.
+    pub fn first_n(&self, n: usize) -> Iter<'_, T> {
+        Iter::new(self.storage.slots(), self.read_position, n.min(self.len))
     }
 
-    #[test]
-    fn full_buffer_error() {
-        let mut buffer = Hoop::with_capacity(2);
-        buffer.write('1');
-        buffer.write('2');
-        assert_eq!(WriteResult::TooMany, buffer.write('3'));
+    /// Items as a pair of contiguous slices, from the oldest to the newest. Second slice is empty
+    /// unless items wrap around the end of storage.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 4> = ArrayHoop::new();
+    /// for i in 0..6 {
+    ///     buffer.overwrite(i);
+    /// }
+    /// assert_eq!((&[2, 3][..], &[4, 5][..]), buffer.as_slices());
+    /// assert_eq!(&[2, 3, 4, 5], buffer.make_contiguous());
+    /// assert_eq!((&[2, 3, 4, 5][..], &[][..]), buffer.as_slices());
+    /// ```
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        self.iter().as_slices()
     }
 
-    #[test]
-    fn overwrite_item_in_non_full_buffer() {
-        let mut buffer = Hoop::with_capacity(2);
-        buffer.write('1');
-        buffer.overwrite('2');
-        assert_eq!(Some('1'), buffer.pop());
-        assert_eq!(Some('2'), buffer.pop());
-        assert_eq!(None, buffer.pop());
+    /// Mutable pair of contiguous slices, from the oldest to the newest.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (head, tail) = self.live_slots_mut();
+        unsafe { (assume_init_slice_mut(head), assume_init_slice_mut(tail)) }
     }
 
-    #[test]
-    fn overwrite_item_in_full_buffer() {
-        let mut buffer = Hoop::with_capacity(2);
-        buffer.write('1');
-        buffer.write('2');
-        buffer.overwrite('A');
-        assert_eq!(Some('2'), buffer.pop());
-        assert_eq!(Some('A'), buffer.pop());
+    /// Rotate storage in place so that every item is in one contiguous slice, from the oldest to
+    /// the newest. Nothing is moved if items don't wrap around already, otherwise this is `O(n)`
+    /// in capacity.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if self.read_position + self.len > self.slot_count() {
+            let read_position = self.read_position;
+            self.storage.slots_mut().rotate_left(read_position);
+            self.read_position = 0;
+            self.write_position = self.physical(self.len);
+        }
+        self.as_mut_slices().0
     }
 
-    #[test]
-    fn iterator_sequence() {
-        let mut buffer = Hoop::with_capacity(2);
-        buffer.write('1');
-        buffer.write('2');
+    /// Create non-consuming iterator that allows modifying items in place.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 3> = ArrayHoop::new();
+    /// for i in 0..5 {
+    ///     buffer.overwrite(i);
+    /// }
+    /// for item in buffer.iter_mut() {
+    ///     *item *= 10;
+    /// }
+    /// assert_eq!(Some(20), buffer.pop());
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (head, tail) = self.live_slots_mut();
+        IterMut {
+            head: head.iter_mut(),
+            tail: tail.iter_mut(),
+        }
+    }
 
-        let expected = vec!['1', '2'];
+    /// Remove every item from a buffer, yielding them from the oldest to the newest. Buffer is
+    /// left empty even if iterator is dropped before it's exhausted.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 3> = ArrayHoop::new();
+    /// for i in 0..5 {
+    ///     buffer.overwrite(i);
+    /// }
+    /// assert_eq!(Some(2), buffer.drain().next());
+    /// assert!(buffer.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Drain<'_, T, S> {
+        Drain::new(self, ..)
+    }
 
-        let result: Vec<char> = buffer.iter().cloned().collect();
-        assert_eq!(expected, result);
+    /// Remove items in `range` of positions after the oldest one, yielding them in order.
+    /// Remaining items keep their order, whichever side of the range is shorter is moved to
+    /// close the gap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` starts after it ends or ends past `len()`.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<i32, 4> = ArrayHoop::new();
+    /// for i in 0..4 {
+    ///     buffer.write(i);
+    /// }
+    /// assert_eq!(vec![1, 2], buffer.drain_range(1..3).collect::<Vec<_>>());
+    /// assert_eq!(vec![0, 3], buffer.into_iter().collect::<Vec<_>>());
+    /// ```
+    pub fn drain_range<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, S> {
+        Drain::new(self, range)
     }
 
-    #[test]
-    fn iterator_warped() {
-        let mut buffer = Hoop::with_capacity(2);
-        buffer.write('1');
-        buffer.write('2');
-        buffer.overwrite('A');
+    /// Grant up to `n` free slots right after the newest item to fill in place. Grant is
+    /// shorter when free slots wrap around the end of storage, and empty if buffer is full.
+    ///
+    /// ```
+    /// use hoop::ArrayHoop;
+    ///
+    /// let mut buffer: ArrayHoop<u8, 8> = ArrayHoop::new();
+    /// let mut grant = buffer.write_grant(4);
+    /// grant.buf_init()[..3].copy_from_slice(b"abc");
+    /// grant.commit(3);
+    /// let grant = buffer.read_grant();
+    /// assert_eq!(b"abc", grant.buf());
+    /// grant.release(2);
+    /// assert_eq!(Some(b'c'), buffer.pop());
+    /// ```
+    pub fn write_grant(&mut self, n: usize) -> WriteGrant<'_, T> {
+        WriteGrant::new(self, n)
+    }
 
-        let expected = vec!['2', 'A'];
+    /// Grant the oldest items up to the end of storage, to look at in place and then release.
+    pub fn read_grant(&mut self) -> ReadGrant<'_, T, S> {
+        ReadGrant::new(self)
+    }
 
-        let result: Vec<char> = buffer.iter().cloned().collect();
-        assert_eq!(expected, result);
+    /// Split buffer into lock-free [`spsc::Producer`] and [`spsc::Consumer`] halves. Items
+    /// already in the buffer are kept.
+    #[cfg(feature = "alloc")]
+    pub fn split(self) -> (spsc::Producer<T>, spsc::Consumer<T>) {
+        spsc::split(self)
     }
 
-    // Should Fail to compile
-    /*
-    #[test]
-    fn iterator_read_and_iter() {
-        let mut buffer = Hoop::with_capacity(2);
-        buffer.write('1');
-        buffer.write('2');
+    // Number of slots positions wrap around at, which is never below capacity.
+    #[inline]
+    fn slot_count(&self) -> usize {
+        self.storage.slots().len()
+    }
+
+    fn advance(&self, current: usize) -> usize {
+        advance(self.slot_count(), current)
+    }
+
+    fn retreat(&self, current: usize) -> usize {
+        retreat(self.slot_count(), current)
+    }
+
+    // Initialized slots, from the oldest item up to the end of storage and then the wrapped
+    // part.
+    fn live_slots_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        let read_position = self.read_position;
+        let len = self.len;
+        let (wrapped, unwrapped) = self.storage.slots_mut().split_at_mut(read_position);
+        let head_len = len.min(unwrapped.len());
+        (&mut unwrapped[..head_len], &mut wrapped[..len - head_len])
+    }
+
+    // Slot of item `offset` positions after the oldest one. `offset` must be below capacity.
+    fn physical(&self, offset: usize) -> usize {
+        let idx = self.read_position + offset;
+        if idx >= self.slot_count() {
+            idx - self.slot_count()
+        } else {
+            idx
+        }
+    }
+}
+
+impl<T, S: Storage<T>> Index<usize> for Hoop<T, S> {
+    type Output = T;
+
+    /// Same as [`get`](#method.get), but panics when `index` is out of bounds.
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(item) => item,
+            None => panic!("index {} is out of bounds of {} items", index, self.len),
+        }
+    }
+}
+
+impl<T, S: Storage<T>> IndexMut<usize> for Hoop<T, S> {
+    /// Same as [`get_mut`](#method.get_mut), but panics when `index` is out of bounds.
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len;
+        match self.get_mut(index) {
+            Some(item) => item,
+            None => panic!("index {} is out of bounds of {} items", index, len),
+        }
+    }
+}
+
+/// Extending a buffer works like [`Hoop::overwrite`] on every item, so only the newest items are
+/// kept once buffer is at capacity.
+impl<T, S: Storage<T>> Extend<T> for Hoop<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.overwrite(item);
+        }
+    }
+}
+
+impl<T, S: Storage<T>> Drop for Hoop<T, S> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+fn advance(capacity: usize, current: usize) -> usize {
+    if (current + 1) == capacity {
+        0
+    } else {
+        current + 1
+    }
+}
+
+fn retreat(capacity: usize, current: usize) -> usize {
+    if current == 0 {
+        capacity - 1
+    } else {
+        current - 1
+    }
+}
+
+// `MaybeUninit<T>` has the same layout as `T`, so slice of initialized slots is a slice of items.
+unsafe fn assume_init_slice<T>(slots: &[MaybeUninit<T>]) -> &[T] {
+    slice::from_raw_parts(slots.as_ptr() as *const T, slots.len())
+}
+
+unsafe fn assume_init_slice_mut<T>(slots: &mut [MaybeUninit<T>]) -> &mut [T] {
+    slice::from_raw_parts_mut(slots.as_mut_ptr() as *mut T, slots.len())
+}
+
+/// Non-consuming iterator created by [`Hoop::iter`], [`Hoop::last_n`] and [`Hoop::first_n`].
+pub struct Iter<'data, T: 'data> {
+    slots: &'data [MaybeUninit<T>],
+    // Slot of the first item iterator covers. Offsets below are relative to it, so they keep
+    // their order no matter where storage wraps around.
+    read_position: usize,
+    // Items at offsets in `front..back` are not yielded yet.
+    front: usize,
+    back: usize,
+}
+
+impl<'data, T: 'data> Iterator for Iter<'data, T> {
+    type Item = &'data T;
+    fn next(&mut self) -> Option<&'data T> {
+        // We met back. Everything past it is already taken.
+        if self.front == self.back {
+            return None;
+        }
+        let item = self.item(self.front);
+        self.front += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<&'data T> {
+        if n >= self.back - self.front {
+            self.front = self.back;
+            return None;
+        }
+        self.front += n;
+        self.next()
+    }
+
+    fn last(mut self) -> Option<&'data T> {
+        self.next_back()
+    }
+
+    fn count(self) -> usize {
+        self.back - self.front
+    }
+}
+
+impl<'data, T: 'data> DoubleEndedIterator for Iter<'data, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // We met front. Everything before it is already taken.
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.item(self.back))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<&'data T> {
+        if n >= self.back - self.front {
+            self.back = self.front;
+            return None;
+        }
+        self.back -= n;
+        self.next_back()
+    }
+}
+
+impl<'data, T: 'data> ExactSizeIterator for Iter<'data, T> {}
+
+// Once front meets back they stay together.
+impl<'data, T: 'data> FusedIterator for Iter<'data, T> {}
+
+impl<'data, T: 'data> Iter<'data, T> {
+    // `len` slots starting at `read_position` must be initialized.
+    fn new(slots: &'data [MaybeUninit<T>], read_position: usize, len: usize) -> Self {
+        Iter {
+            slots,
+            read_position,
+            front: 0,
+            back: len,
+        }
+    }
+
+    // Slot of item `offset` positions after `read_position`. `offset` must be below capacity.
+    fn slot(&self, offset: usize) -> usize {
+        let idx = self.read_position + offset;
+        if idx >= self.slots.len() {
+            idx - self.slots.len()
+        } else {
+            idx
+        }
+    }
+
+    fn item(&self, offset: usize) -> &'data T {
+        // Every offset below `back` is initialized.
+        unsafe { self.slots[self.slot(offset)].assume_init_ref() }
+    }
+
+    /// Items that are not yielded yet as a pair of contiguous slices, from the oldest to the
+    /// newest. Second slice is empty unless those items wrap around the end of storage.
+    pub fn as_slices(&self) -> (&'data [T], &'data [T]) {
+        let remaining = self.back - self.front;
+        if remaining == 0 {
+            return (&[], &[]);
+        }
+        let start = self.slot(self.front);
+        let head_len = remaining.min(self.slots.len() - start);
+        let head = &self.slots[start..start + head_len];
+        let tail = &self.slots[..remaining - head_len];
+        unsafe { (assume_init_slice(head), assume_init_slice(tail)) }
+    }
+}
+
+/// Mutable iterator created by [`Hoop::iter_mut`]. Front and back never yield the same item.
+pub struct IterMut<'data, T: 'data> {
+    // Oldest items up to the end of storage.
+    head: slice::IterMut<'data, MaybeUninit<T>>,
+    // Items that wrapped around to the start of storage.
+    tail: slice::IterMut<'data, MaybeUninit<T>>,
+}
+
+impl<'data, T: 'data> Iterator for IterMut<'data, T> {
+    type Item = &'data mut T;
+    fn next(&mut self) -> Option<&'data mut T> {
+        let slot = match self.head.next() {
+            Some(slot) => slot,
+            None => self.tail.next()?,
+        };
+        // Iterator only ever covers initialized slots.
+        Some(unsafe { slot.assume_init_mut() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.head.len() + self.tail.len();
+        (len, Some(len))
+    }
+}
+
+impl<'data, T: 'data> DoubleEndedIterator for IterMut<'data, T> {
+    fn next_back(&mut self) -> Option<&'data mut T> {
+        let slot = match self.tail.next_back() {
+            Some(slot) => slot,
+            None => self.head.next_back()?,
+        };
+        Some(unsafe { slot.assume_init_mut() })
+    }
+}
+
+impl<'data, T: 'data> ExactSizeIterator for IterMut<'data, T> {}
+
+#[must_use]
+/// Result of a write operation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WriteResult {
+    /// Item was written to a buffer.
+    Done,
+    /// Buffer can't take any more items.
+    TooMany,
+}
+
+// Ring of `capacity` slots holding `items`, the oldest one in slot `offset`, so that they wrap
+// around storage once there are enough of them.
+#[cfg(all(test, feature = "alloc"))]
+pub(crate) fn wrapped<T, I: IntoIterator<Item = T>>(
+    capacity: usize,
+    offset: usize,
+    items: I,
+) -> Hoop<T> {
+    assert!(
+        offset < capacity.max(1),
+        "offset {} is out of {} slots",
+        offset,
+        capacity
+    );
+    let mut buffer = Hoop::with_capacity(capacity);
+    buffer.read_position = offset;
+    buffer.write_position = offset;
+    for item in items {
+        let result = buffer.write(item);
+        assert_eq!(WriteResult::Done, result, "more items than {} slots", capacity);
+    }
+    buffer
+}
+
+#[cfg(all(test, feature = "alloc"))]
+#[allow(unused_must_use)]
+mod tests {
+    use super::*;
+    use std::string::String;
+    use std::vec::Vec;
+
+    #[test]
+    fn error_on_read_empty_buffer() {
+        let mut buffer = Hoop::<char>::with_capacity(1);
+        assert_eq!(None, buffer.pop());
+    }
+
+    #[test]
+    fn write_and_read_back_item() {
+        let mut buffer = Hoop::with_capacity(1);
+        buffer.write('1');
+        assert_eq!(Some('1'), buffer.pop());
+        assert_eq!(None, buffer.pop());
+    }
+
+    #[test]
+    fn write_and_read_back_multiple_items() {
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write('1');
+        buffer.write('2');
+        assert_eq!(Some('1'), buffer.pop());
+        assert_eq!(Some('2'), buffer.pop());
+        assert_eq!(None, buffer.pop());
+    }
+
+    #[test]
+    fn alternate_write_and_read() {
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write('1');
+        assert_eq!(Some('1'), buffer.pop());
+        buffer.write('2');
+        assert_eq!(Some('2'), buffer.pop());
+    }
+
+    #[test]
+    fn clear_buffer() {
+        let mut buffer = Hoop::with_capacity(3);
+        buffer.write('1');
+        buffer.write('2');
+        buffer.write('3');
+        buffer.clear();
+        assert_eq!(None, buffer.pop());
+        buffer.write('1');
+        buffer.write('2');
+        assert_eq!(Some('1'), buffer.pop());
+        buffer.write('3');
+        assert_eq!(Some('2'), buffer.pop());
+    }
+
+    #[test]
+    fn full_buffer_error() {
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write('1');
+        buffer.write('2');
+        assert_eq!(WriteResult::TooMany, buffer.write('3'));
+    }
+
+    #[test]
+    fn overwrite_item_in_non_full_buffer() {
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write('1');
+        buffer.overwrite('2');
+        assert_eq!(Some('1'), buffer.pop());
+        assert_eq!(Some('2'), buffer.pop());
+        assert_eq!(None, buffer.pop());
+    }
+
+    #[test]
+    fn overwrite_item_in_full_buffer() {
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write('1');
+        buffer.write('2');
+        buffer.overwrite('A');
+        assert_eq!(Some('2'), buffer.pop());
+        assert_eq!(Some('A'), buffer.pop());
+    }
+
+    #[test]
+    fn iterator_sequence() {
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write('1');
+        buffer.write('2');
+
+        let expected = vec!['1', '2'];
+
+        let result: Vec<char> = buffer.iter().cloned().collect();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn iterator_warped() {
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write('1');
+        buffer.write('2');
+        buffer.overwrite('A');
+
+        let expected = vec!['2', 'A'];
+
+        let result: Vec<char> = buffer.iter().cloned().collect();
+        assert_eq!(expected, result);
+    }
+
+    // Should Fail to compile
+    /*
+    #[test]
+    fn iterator_read_and_iter() {
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write('1');
+        buffer.write('2');
 
         let mut one = buffer.iter().take(1);
 
@@ -335,4 +1297,817 @@ mod tests {
         assert_eq!(None, iter.next());
         assert_eq!(None, iter.next_back());
     }
+
+    #[test]
+    fn non_clone_items() {
+        struct Handle(u8);
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write(Handle(1));
+        buffer.write(Handle(2));
+        buffer.overwrite(Handle(3));
+        assert_eq!(Some(2), buffer.pop().map(|h| h.0));
+        assert_eq!(Some(3), buffer.pop().map(|h| h.0));
+        assert!(buffer.pop().is_none());
+    }
+
+    #[test]
+    fn drops_only_live_items() {
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        {
+            let mut buffer = Hoop::with_capacity(3);
+            buffer.write(token.clone());
+            buffer.write(token.clone());
+            assert_eq!(3, Rc::strong_count(&token));
+            drop(buffer.pop());
+            assert_eq!(2, Rc::strong_count(&token));
+        }
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn overwrite_drops_evicted_item() {
+        use std::rc::Rc;
+
+        let first = Rc::new('1');
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.write(first.clone());
+        buffer.write(Rc::new('2'));
+        buffer.overwrite(Rc::new('3'));
+        assert_eq!(1, Rc::strong_count(&first));
+        buffer.overwrite(Rc::new('4'));
+        buffer.overwrite(Rc::new('5'));
+        let result: Vec<char> = buffer.iter().map(|c| **c).collect();
+        assert_eq!(vec!['4', '5'], result);
+    }
+
+    #[test]
+    fn wraparound_drop_and_clear() {
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        let mut buffer = Hoop::with_capacity(3);
+        for _ in 0..7 {
+            buffer.overwrite(token.clone());
+            buffer.pop();
+            buffer.write(token.clone());
+        }
+        assert_eq!(4, Rc::strong_count(&token));
+        buffer.clear();
+        assert_eq!(1, Rc::strong_count(&token));
+        buffer.write(token.clone());
+        buffer.write(token.clone());
+        drop(buffer);
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn capacity_is_exactly_what_was_asked_for() {
+        for capacity in 0..40 {
+            let mut buffer = Hoop::with_capacity(capacity);
+            assert_eq!(capacity, buffer.capacity());
+            for i in 0..capacity {
+                assert_eq!(WriteResult::Done, buffer.write(i));
+            }
+            assert!(buffer.is_full());
+            assert_eq!(WriteResult::TooMany, buffer.write(capacity));
+            // Wrap around a few times.
+            for i in 0..3 * capacity {
+                buffer.overwrite(capacity + i);
+            }
+            let expected: Vec<usize> = (3 * capacity..4 * capacity).collect();
+            let result: Vec<usize> = buffer.iter().cloned().collect();
+            assert_eq!(expected, result);
+        }
+    }
+
+    #[test]
+    fn zero_capacity_is_always_full() {
+        let mut buffer = Hoop::with_capacity(0);
+        assert_eq!(0, buffer.capacity());
+        assert!(buffer.is_empty());
+        assert!(buffer.is_full());
+        assert_eq!(0, buffer.free_slots());
+        assert_eq!(WriteResult::TooMany, buffer.write('1'));
+        buffer.overwrite('1');
+        assert_eq!(0, buffer.len());
+        assert_eq!(None, buffer.pop());
+        assert_eq!(None, buffer.iter().next());
+        assert_eq!(None, buffer.iter().next_back());
+        buffer.clear();
+        assert_eq!(None, buffer.pop());
+    }
+
+    #[test]
+    fn zero_capacity_drops_overwritten_item() {
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        let mut buffer = Hoop::with_capacity(0);
+        buffer.overwrite(token.clone());
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn single_slot_buffer() {
+        let mut buffer = Hoop::with_capacity(1);
+        buffer.overwrite('1');
+        buffer.overwrite('2');
+        assert!(buffer.is_full());
+        assert_eq!(vec!['2'], buffer.iter().cloned().collect::<Vec<char>>());
+        assert_eq!(vec!['2'], buffer.iter().rev().cloned().collect::<Vec<char>>());
+        assert_eq!(Some('2'), buffer.pop());
+        assert_eq!(None, buffer.pop());
+    }
+
+    #[test]
+    fn get_relative_to_oldest_and_newest() {
+        let mut buffer = Hoop::with_capacity(4);
+        assert_eq!(None, buffer.get(0));
+        assert_eq!(None, buffer.get_back(0));
+        for i in 0..7 {
+            buffer.overwrite(i);
+        }
+        buffer.pop();
+        assert_eq!(Some(&4), buffer.get(0));
+        assert_eq!(Some(&6), buffer.get(2));
+        assert_eq!(None, buffer.get(3));
+        assert_eq!(Some(&6), buffer.get_back(0));
+        assert_eq!(Some(&4), buffer.get_back(2));
+        assert_eq!(None, buffer.get_back(3));
+    }
+
+    #[test]
+    fn get_mut_and_index_mut() {
+        let mut buffer = Hoop::with_capacity(3);
+        for i in 0..5 {
+            buffer.overwrite(i);
+        }
+        *buffer.get_mut(0).unwrap() += 10;
+        *buffer.get_back_mut(0).unwrap() += 20;
+        buffer[1] += 30;
+        assert_eq!(None, buffer.get_mut(3));
+        assert_eq!(None, buffer.get_back_mut(3));
+        assert_eq!(vec![12, 33, 24], buffer.iter().cloned().collect::<Vec<i32>>());
+        assert_eq!(33, buffer[1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 is out of bounds of 2 items")]
+    fn index_out_of_bounds() {
+        let mut buffer = Hoop::with_capacity(3);
+        buffer.write('1');
+        buffer.write('2');
+        let _ = buffer[2];
+    }
+
+    #[test]
+    fn iter_mut_modifies_in_place() {
+        let mut buffer = Hoop::with_capacity(4);
+        for i in 0..6 {
+            buffer.overwrite(i);
+        }
+        for item in buffer.iter_mut() {
+            *item += 10;
+        }
+        assert_eq!(vec![12, 13, 14, 15], buffer.iter().cloned().collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn iter_mut_both_ways_never_cross() {
+        let mut buffer = Hoop::with_capacity(4);
+        for c in "x1234".chars() {
+            buffer.overwrite(c);
+        }
+        let mut iter = buffer.iter_mut();
+        assert_eq!(4, iter.len());
+        assert_eq!(Some(&mut '1'), iter.next());
+        assert_eq!(Some(&mut '4'), iter.next_back());
+        assert_eq!(Some(&mut '3'), iter.next_back());
+        assert_eq!(1, iter.len());
+        assert_eq!(Some(&mut '2'), iter.next());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+        assert_eq!(0, iter.len());
+    }
+
+    #[test]
+    fn iter_mut_on_empty_and_unwrapped() {
+        let mut buffer = Hoop::<char>::with_capacity(3);
+        assert_eq!(None, buffer.iter_mut().next());
+        buffer.write('1');
+        buffer.write('2');
+        let result: Vec<char> = buffer.iter_mut().rev().map(|c| *c).collect();
+        assert_eq!(vec!['2', '1'], result);
+    }
+
+    #[test]
+    fn last_and_first_n_windows() {
+        for start in 0..4 {
+            let buffer = wrapped(4, start, 0..4);
+            for n in 0..6 {
+                let newest: Vec<usize> = (4 - n.min(4)..4).collect();
+                let window = buffer.last_n(n);
+                assert_eq!(newest.len(), window.len());
+                let (head, tail) = window.as_slices();
+                assert_eq!(newest, [head, tail].concat());
+                assert_eq!(newest, window.cloned().collect::<Vec<usize>>());
+                let reversed: Vec<usize> = buffer.last_n(n).rev().cloned().collect();
+                assert_eq!(newest.iter().rev().cloned().collect::<Vec<usize>>(), reversed);
+
+                let oldest: Vec<usize> = (0..n.min(4)).collect();
+                let (head, tail) = buffer.first_n(n).as_slices();
+                assert_eq!(oldest, [head, tail].concat());
+                assert_eq!(oldest, buffer.first_n(n).cloned().collect::<Vec<usize>>());
+            }
+        }
+    }
+
+    #[test]
+    fn iter_as_slices_shrink_as_consumed() {
+        let buffer = wrapped(4, 3, "234".chars());
+        let mut iter = buffer.iter();
+        assert_eq!((&['2'][..], &['3', '4'][..]), iter.as_slices());
+        iter.next();
+        iter.next();
+        assert_eq!((&['4'][..], &[][..]), iter.as_slices());
+        iter.next();
+        assert_eq!((&[][..], &[][..]), iter.as_slices());
+    }
+
+    #[test]
+    fn as_slices_split_at_wraparound() {
+        let mut buffer = Hoop::with_capacity(4);
+        assert_eq!((&[][..], &[][..]), buffer.as_slices());
+        for i in 0..3 {
+            buffer.write(i);
+        }
+        assert_eq!((&[0, 1, 2][..], &[][..]), buffer.as_slices());
+        buffer.pop();
+        buffer.write(3);
+        buffer.write(4);
+        assert_eq!((&[1, 2, 3][..], &[4][..]), buffer.as_slices());
+        {
+            let (head, tail) = buffer.as_mut_slices();
+            head[0] = 10;
+            tail[0] = 40;
+        }
+        assert_eq!(vec![10, 2, 3, 40], buffer.iter().cloned().collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn make_contiguous_rotates_in_place() {
+        for start in 0..5 {
+            for len in 0..6 {
+                let mut buffer = wrapped(5, start, 0..len);
+                let expected: Vec<usize> = (0..len).collect();
+                assert_eq!(&expected[..], buffer.make_contiguous());
+                assert_eq!((&expected[..], &[][..]), buffer.as_slices());
+                // Positions stay consistent after rotating.
+                if buffer.write(99) == WriteResult::Done {
+                    assert_eq!(Some(&99), buffer.get_back(0));
+                }
+                assert_eq!(Some(if len > 0 { 0 } else { 99 }), buffer.pop());
+            }
+        }
+    }
+
+    #[test]
+    fn extend_from_slice_stops_when_full() {
+        let mut buffer = Hoop::with_capacity(4);
+        buffer.write(0);
+        buffer.pop();
+        assert_eq!(0, buffer.extend_from_slice(&[]));
+        assert_eq!(3, buffer.extend_from_slice(&[1, 2, 3]));
+        assert_eq!(1, buffer.extend_from_slice(&[4, 5, 6]));
+        assert_eq!(0, buffer.extend_from_slice(&[7]));
+        assert_eq!((&[1, 2, 3][..], &[4][..]), buffer.as_slices());
+        assert_eq!(0, Hoop::with_capacity(0).extend_from_slice(&[1]));
+    }
+
+    #[test]
+    fn overwrite_from_slice_keeps_newest() {
+        let mut buffer = Hoop::with_capacity(3);
+        buffer.overwrite_from_slice(&[1, 2]);
+        assert_eq!((&[1, 2][..], &[][..]), buffer.as_slices());
+        buffer.overwrite_from_slice(&[3, 4]);
+        assert_eq!((&[2, 3][..], &[4][..]), buffer.as_slices());
+        buffer.overwrite_from_slice(&[5, 6, 7, 8, 9]);
+        assert_eq!((&[7, 8][..], &[9][..]), buffer.as_slices());
+        let mut empty = Hoop::with_capacity(0);
+        empty.overwrite_from_slice(&[1, 2]);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn extend_overwrites() {
+        let mut buffer = Hoop::with_capacity(3);
+        buffer.extend(0..2);
+        buffer.extend(vec![2, 3]);
+        assert_eq!((&[1, 2][..], &[3][..]), buffer.as_slices());
+    }
+
+    #[test]
+    fn pop_into_copies_across_wraparound() {
+        for start in 0..4 {
+            for out_len in 0..6 {
+                let mut buffer = wrapped(4, start, 1..5);
+                let mut out = vec![0; out_len];
+                let popped = buffer.pop_into(&mut out);
+                assert_eq!(out_len.min(4), popped);
+                assert_eq!(&[1, 2, 3, 4][..popped], &out[..popped]);
+                assert_eq!(4 - popped, buffer.len());
+                assert_eq!(buffer.free_slots(), buffer.extend_from_slice(&[5, 6, 7, 8]));
+                let expected: Vec<usize> = (popped + 1..popped + 5).collect();
+                let (head, tail) = buffer.as_slices();
+                assert_eq!(expected, [head, tail].concat());
+            }
+        }
+    }
+
+    #[test]
+    fn pop_n_removes_even_if_not_consumed() {
+        let mut buffer = Hoop::with_capacity(4);
+        buffer.extend_from_slice(&['1', '2', '3']);
+        assert_eq!(vec!['1'], buffer.pop_n(1).collect::<Vec<char>>());
+        buffer.pop_n(1);
+        assert_eq!(vec!['3'], buffer.pop_n(10).collect::<Vec<char>>());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn try_write_hands_item_back() {
+        let mut buffer = Hoop::with_capacity(2);
+        assert_eq!(Ok(()), buffer.try_write(String::from("1")));
+        assert_eq!(Ok(()), buffer.try_write(String::from("2")));
+        assert_eq!(Err(String::from("3")), buffer.try_write(String::from("3")));
+        assert_eq!(2, buffer.len());
+        assert_eq!(Err(1), Hoop::with_capacity(0).try_write(1));
+    }
+
+    #[test]
+    fn push_overwrite_returns_evicted() {
+        let mut buffer = Hoop::with_capacity(2);
+        assert_eq!(None, buffer.push_overwrite('1'));
+        assert_eq!(None, buffer.push_overwrite('2'));
+        assert_eq!(Some('1'), buffer.push_overwrite('3'));
+        assert_eq!(Some('2'), buffer.push_overwrite('4'));
+        assert_eq!(vec!['3', '4'], buffer.iter().cloned().collect::<Vec<char>>());
+        assert_eq!(Some('5'), Hoop::with_capacity(0).push_overwrite('5'));
+    }
+
+    #[test]
+    fn front_and_back_follow_wraparound() {
+        let mut buffer = Hoop::with_capacity(3);
+        assert_eq!(None, buffer.front());
+        assert_eq!(None, buffer.back_mut());
+        buffer.write('1');
+        assert_eq!(Some(&'1'), buffer.front());
+        assert_eq!(Some(&'1'), buffer.back());
+        for c in "234".chars() {
+            buffer.overwrite(c);
+        }
+        assert_eq!(Some(&'2'), buffer.front());
+        assert_eq!(Some(&'4'), buffer.back());
+        *buffer.front_mut().unwrap() = 'a';
+        *buffer.back_mut().unwrap() = 'c';
+        assert_eq!((&['a', '3'][..], &['c'][..]), buffer.as_slices());
+    }
+
+    #[test]
+    fn pop_back_takes_newest() {
+        let mut buffer = Hoop::with_capacity(3);
+        assert_eq!(None, buffer.pop_back());
+        for i in 0..5 {
+            buffer.overwrite(i);
+        }
+        assert_eq!(Some(4), buffer.pop_back());
+        assert_eq!(Some(3), buffer.pop_back());
+        buffer.write(5);
+        assert_eq!(vec![2, 5], buffer.iter().cloned().collect::<Vec<i32>>());
+        assert_eq!(Some(5), buffer.pop_back());
+        assert_eq!(Some(2), buffer.pop_back());
+        assert_eq!(None, buffer.pop_back());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn push_front_wraps_backwards() {
+        let mut buffer = Hoop::with_capacity(3);
+        assert_eq!(Ok(()), buffer.try_push_front('2'));
+        assert_eq!(WriteResult::Done, buffer.push_front('1'));
+        buffer.write('3');
+        assert_eq!(Err('0'), buffer.try_push_front('0'));
+        assert_eq!(WriteResult::TooMany, buffer.push_front('0'));
+        assert_eq!((&['1', '2'][..], &['3'][..]), buffer.as_slices());
+        assert_eq!(Some('1'), buffer.pop());
+        assert_eq!(Some('3'), buffer.pop_back());
+        assert_eq!(Some('2'), buffer.pop());
+    }
+
+    #[test]
+    fn overwrite_front_evicts_newest() {
+        use std::rc::Rc;
+        let token = Rc::new(());
+        let mut buffer = Hoop::with_capacity(2);
+        buffer.overwrite_front(Rc::clone(&token));
+        buffer.overwrite_front(Rc::clone(&token));
+        buffer.overwrite_front(Rc::clone(&token));
+        assert_eq!(3, Rc::strong_count(&token));
+        let mut empty = Hoop::with_capacity(0);
+        empty.overwrite_front(Rc::clone(&token));
+        assert_eq!(3, Rc::strong_count(&token));
+
+        let mut buffer = Hoop::with_capacity(3);
+        for i in 0..5 {
+            buffer.overwrite_front(i);
+        }
+        assert_eq!((&[4, 3][..], &[2][..]), buffer.as_slices());
+    }
+
+    #[test]
+    fn resize_keeps_order() {
+        for start in 0..4 {
+            for len in 0..5 {
+                for new_capacity in 0..7 {
+                    let mut buffer = wrapped(4, start, 0..len);
+                    buffer.resize(new_capacity);
+                    assert_eq!(new_capacity, buffer.capacity());
+                    let kept: Vec<usize> = (len - len.min(new_capacity)..len).collect();
+                    assert_eq!(kept, buffer.iter().cloned().collect::<Vec<usize>>());
+                    let added = buffer.extend_from_slice(&[10, 11, 12, 13, 14, 15, 16]);
+                    assert_eq!(new_capacity - kept.len(), added);
+                    let expected: Vec<usize> = kept.iter().cloned().chain(10..10 + added).collect();
+                    assert_eq!(expected, buffer.iter().cloned().collect::<Vec<usize>>());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shrink_to_returns_evicted() {
+        let mut buffer = Hoop::with_capacity(4);
+        for i in 0..6 {
+            buffer.overwrite(i);
+        }
+        assert!(buffer.shrink_to(6).is_empty());
+        assert_eq!(4, buffer.capacity());
+        assert_eq!(vec![2, 3, 4], buffer.shrink_to(1));
+        assert_eq!(vec![5], buffer.iter().cloned().collect::<Vec<i32>>());
+        assert_eq!(vec![5], buffer.shrink_to(0));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn grow_and_reserve() {
+        let mut buffer = Hoop::with_capacity(0);
+        buffer.grow(2);
+        assert_eq!(2, buffer.capacity());
+        buffer.write('1');
+        buffer.reserve_exact(1);
+        assert_eq!(2, buffer.capacity());
+        buffer.reserve_exact(3);
+        assert_eq!(4, buffer.capacity());
+        buffer.reserve(4);
+        assert_eq!(8, buffer.capacity());
+        buffer.reserve(10);
+        assert_eq!(16, buffer.capacity());
+        assert_eq!(vec!['1'], buffer.iter().cloned().collect::<Vec<char>>());
+    }
+
+    #[test]
+    fn resize_drops_evicted() {
+        use std::rc::Rc;
+        let token = Rc::new(());
+        let mut buffer = Hoop::with_capacity(3);
+        for _ in 0..3 {
+            buffer.write(Rc::clone(&token));
+        }
+        buffer.resize(1);
+        assert_eq!(2, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn iter_is_exact_size() {
+        let mut buffer = Hoop::with_capacity(4);
+        for i in 0..6 {
+            buffer.overwrite(i);
+        }
+        buffer.pop();
+        let mut iter = buffer.iter();
+        assert_eq!((3, Some(3)), iter.size_hint());
+        iter.next_back();
+        assert_eq!(2, iter.len());
+        iter.next();
+        iter.next();
+        assert_eq!(0, iter.len());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+        assert_eq!(3, buffer.iter().count());
+    }
+
+    #[test]
+    fn iter_nth_skips_over_wraparound() {
+        for capacity in 1..6 {
+            for start in 0..capacity {
+                for len in 0..capacity + 1 {
+                    let buffer = wrapped(capacity, start, 0..len);
+                    for n in 0..len + 2 {
+                        let mut iter = buffer.iter();
+                        assert_eq!(buffer.get(n), iter.nth(n));
+                        assert_eq!(buffer.get(n + 1), iter.next());
+                        let mut iter = buffer.iter();
+                        assert_eq!(buffer.get_back(n), iter.nth_back(n));
+                        assert_eq!(buffer.get_back(n + 1), iter.next_back());
+                    }
+                    if len >= 3 {
+                        let mut iter = buffer.iter();
+                        assert_eq!(Some(&1), iter.nth(1));
+                        assert_eq!(len - 2, iter.len());
+                        assert_eq!(Some(&(len - 1)), iter.nth_back(0));
+                        assert_eq!(None, iter.nth_back(len - 3));
+                        assert_eq!(None, iter.next());
+                    }
+                    assert_eq!(buffer.get_back(0), buffer.iter().last());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(unused_must_use)]
+mod array_tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn array_hoop_write_and_overwrite() {
+        let mut buffer = ArrayHoop::<char, 3>::new();
+        assert_eq!(3, buffer.capacity());
+        buffer.write('1');
+        buffer.write('2');
+        buffer.write('3');
+        assert_eq!(WriteResult::TooMany, buffer.write('4'));
+        buffer.overwrite('4');
+        assert_eq!(Some('2'), buffer.pop());
+        let result: Vec<char> = buffer.iter().cloned().collect();
+        assert_eq!(vec!['3', '4'], result);
+    }
+
+    #[test]
+    fn array_hoop_drops_live_items() {
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        {
+            let mut buffer: ArrayHoop<Rc<()>, 2> = ArrayHoop::default();
+            for _ in 0..5 {
+                buffer.overwrite(token.clone());
+            }
+            assert_eq!(3, Rc::strong_count(&token));
+        }
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn zero_sized_array_hoop() {
+        let mut buffer = ArrayHoop::<char, 0>::new();
+        assert_eq!(WriteResult::TooMany, buffer.write('1'));
+        buffer.overwrite('1');
+        assert_eq!(None, buffer.pop());
+        assert_eq!(0, buffer.iter().count());
+    }
+
+    #[test]
+    fn array_hoop_in_static_context() {
+        const EMPTY: ArrayHoop<u8, 4> = ArrayHoop::new();
+        let mut buffer = EMPTY;
+        buffer.write(1);
+        assert_eq!(Some(1), buffer.pop());
+    }
+}
+
+#[cfg(test)]
+#[allow(unused_must_use)]
+mod ref_tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn from_uninit_slots() {
+        let mut memory: [MaybeUninit<char>; 3] = [MaybeUninit::uninit(); 3];
+        let mut buffer = HoopRef::from_uninit(&mut memory[..2]);
+        assert_eq!(2, buffer.capacity());
+        buffer.write('1');
+        buffer.write('2');
+        assert_eq!(WriteResult::TooMany, buffer.write('3'));
+        buffer.overwrite('3');
+        let result: Vec<char> = buffer.iter().cloned().collect();
+        assert_eq!(vec!['2', '3'], result);
+    }
+
+    #[test]
+    fn from_initialized_slice() {
+        let mut memory = [0u8; 4];
+        {
+            let mut buffer = HoopRef::from_slice(&mut memory);
+            assert_eq!(None, buffer.pop());
+            for i in 1..7 {
+                buffer.overwrite(i);
+            }
+            assert_eq!(Some(3), buffer.pop());
+        }
+        assert_eq!([5, 6, 3, 4], memory);
+    }
+
+    #[test]
+    fn drops_live_items_in_place() {
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        let mut memory: [MaybeUninit<Rc<()>>; 2] = [const { MaybeUninit::uninit() }; 2];
+        {
+            let mut buffer = HoopRef::from_uninit(&mut memory);
+            for _ in 0..3 {
+                buffer.overwrite(token.clone());
+            }
+            assert_eq!(3, Rc::strong_count(&token));
+        }
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+#[allow(unused_must_use)]
+mod proptests {
+    use super::*;
+    use model_tests::{apply, Op};
+    use proptest::prelude::*;
+    use std::collections::VecDeque;
+
+    fn op() -> impl Strategy<Value = Op<u8>> {
+        prop_oneof![
+            any::<u8>().prop_map(Op::Write),
+            any::<u8>().prop_map(Op::Overwrite),
+            Just(Op::Pop),
+            Just(Op::PopBack),
+            any::<u8>().prop_map(Op::PushFront),
+            any::<u8>().prop_map(Op::OverwriteFront),
+            Just(Op::Clear),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn len_agrees_with_model(capacity in 0..8usize, ops in prop::collection::vec(op(), 0..64)) {
+            let mut buffer = Hoop::with_capacity(capacity);
+            let mut model = VecDeque::new();
+            for op in ops {
+                apply(op, &mut buffer, &mut model);
+                prop_assert_eq!(model.len(), buffer.len());
+                prop_assert_eq!(buffer.iter().len(), buffer.len());
+                prop_assert_eq!(model.is_empty(), buffer.is_empty());
+                prop_assert_eq!(model.len() == capacity, buffer.is_full());
+                prop_assert_eq!(capacity - model.len(), buffer.free_slots());
+                let (head, tail) = buffer.as_slices();
+                prop_assert_eq!(model.iter().cloned().collect::<Vec<u8>>(), [head, tail].concat());
+                let forward: Vec<u8> = buffer.iter().cloned().collect();
+                prop_assert_eq!(model.iter().cloned().collect::<Vec<u8>>(), forward);
+                let backward: Vec<u8> = buffer.iter().rev().cloned().collect();
+                prop_assert_eq!(model.iter().rev().cloned().collect::<Vec<u8>>(), backward);
+            }
+        }
+    }
+}
+
+// Every sequence of operations up to a fixed length on small buffers, checked against
+// `VecDeque`. For each reached state every interleaving of `next` and `next_back` must yield
+// each item exactly once.
+#[cfg(all(test, feature = "std", not(miri)))]
+#[allow(unused_must_use)]
+mod model_tests {
+    use super::*;
+    use core::fmt::Debug;
+    use std::collections::VecDeque;
+
+    const OPS: usize = 7;
+    const DEPTH: u32 = 6;
+
+    #[derive(Clone, Debug)]
+    pub(crate) enum Op<T> {
+        Write(T),
+        Overwrite(T),
+        Pop,
+        PopBack,
+        PushFront(T),
+        OverwriteFront(T),
+        Clear,
+    }
+
+    // Apply `op` to `buffer` and the same change to `model`. Popped items must agree.
+    pub(crate) fn apply<T>(op: Op<T>, buffer: &mut Hoop<T>, model: &mut VecDeque<T>)
+    where
+        T: Copy + Debug + PartialEq,
+    {
+        let capacity = buffer.capacity();
+        match op {
+            Op::Write(item) => {
+                buffer.write(item);
+                if model.len() < capacity {
+                    model.push_back(item);
+                }
+            }
+            Op::Overwrite(item) => {
+                buffer.overwrite(item);
+                if capacity > 0 {
+                    if model.len() == capacity {
+                        model.pop_front();
+                    }
+                    model.push_back(item);
+                }
+            }
+            Op::Pop => assert_eq!(model.pop_front(), buffer.pop()),
+            Op::PopBack => assert_eq!(model.pop_back(), buffer.pop_back()),
+            Op::PushFront(item) => {
+                buffer.push_front(item);
+                if model.len() < capacity {
+                    model.push_front(item);
+                }
+            }
+            Op::OverwriteFront(item) => {
+                buffer.overwrite_front(item);
+                if capacity > 0 {
+                    if model.len() == capacity {
+                        model.pop_back();
+                    }
+                    model.push_front(item);
+                }
+            }
+            Op::Clear => {
+                buffer.clear();
+                model.clear();
+            }
+        }
+    }
+
+    // Operation number `index` out of `OPS`, carrying `item` if it adds one.
+    fn op(index: usize, item: usize) -> Op<usize> {
+        match index {
+            0 => Op::Write(item),
+            1 => Op::Overwrite(item),
+            2 => Op::Pop,
+            3 => Op::PopBack,
+            4 => Op::PushFront(item),
+            5 => Op::OverwriteFront(item),
+            _ => Op::Clear,
+        }
+    }
+
+    // Bit `i` of `pattern` picks `next_back` over `next` for the `i`-th step.
+    fn check_interleavings(buffer: &Hoop<usize>, model: &VecDeque<usize>) {
+        let len = model.len();
+        for pattern in 0..1usize << (len + 1) {
+            let mut iter = buffer.iter();
+            let mut front = 0;
+            let mut back = len;
+            for step in 0..len + 1 {
+                assert_eq!(back - front, iter.len());
+                let (head, tail) = iter.as_slices();
+                assert!(head.iter().chain(tail).eq(model.range(front..back)));
+                if pattern & (1 << step) == 0 {
+                    let expected = model.get(front).filter(|_| front < back);
+                    assert_eq!(expected, iter.next());
+                    front += usize::from(expected.is_some());
+                } else {
+                    let expected = back.checked_sub(1).filter(|&i| i >= front);
+                    assert_eq!(expected.map(|i| &model[i]), iter.next_back());
+                    back = expected.unwrap_or(back);
+                }
+            }
+            assert_eq!(None, iter.next());
+            assert_eq!(None, iter.next_back());
+        }
+    }
+
+    #[test]
+    fn every_sequence_matches_vec_deque() {
+        for capacity in 0..5 {
+            for depth in 0..DEPTH + 1 {
+                for sequence in 0..OPS.pow(depth) {
+                    let mut buffer = Hoop::with_capacity(capacity);
+                    let mut model = VecDeque::new();
+                    let mut ops = sequence;
+                    for item in 0..depth as usize {
+                        apply(op(ops % OPS, item), &mut buffer, &mut model);
+                        ops /= OPS;
+                    }
+                    // Shorter sequences already covered every prefix.
+                    assert_eq!(model.len(), buffer.len());
+                    check_interleavings(&buffer, &model);
+                }
+            }
+        }
+    }
 }
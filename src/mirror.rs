@@ -0,0 +1,390 @@
+//! Ring whose items are always one contiguous slice. Requires `mirror` feature.
+//!
+//! On Linux storage is a `memfd` mapped twice in a row, so the slot right after the last one is
+//! the first one again and items that wrap around storage are still contiguous in memory. Where
+//! mapping isn't available storage falls back to a plain heap allocation and
+//! [`Hoop::as_slice`](../struct.Hoop.html#method.as_slice) rotates items in place instead.
+//!
+//! ```
+//! use hoop::mirror::MirroredHoop;
+//!
+//! let mut buffer = MirroredHoop::mirrored(4);
+//! for i in 0..6 {
+//!     buffer.overwrite(i);
+//! }
+//! assert_eq!(&[2, 3, 4, 5], buffer.as_slice());
+//! ```
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use core::slice;
+
+use {Hoop, Storage};
+
+/// [`Hoop`] over [`MirroredStorage`].
+pub type MirroredHoop<T> = Hoop<T, MirroredStorage<T>>;
+
+/// Storage mapped twice in a row, or a heap allocation if that's not possible.
+pub struct MirroredStorage<T> {
+    inner: Inner<T>,
+    // Requested capacity. Mapped storage usually has more slots than that.
+    capacity: usize,
+}
+
+enum Inner<T> {
+    // Slots are followed by their own mirror, so `2 * slots` slots are readable from `ptr`.
+    #[cfg(all(target_os = "linux", not(miri)))]
+    Mapped {
+        ptr: *mut MaybeUninit<T>,
+        slots: usize,
+    },
+    Heap(Vec<MaybeUninit<T>>),
+}
+
+// Mapping is owned just like a `Vec` would be.
+unsafe impl<T: Send> Send for MirroredStorage<T> {}
+unsafe impl<T: Sync> Sync for MirroredStorage<T> {}
+
+impl<T> MirroredStorage<T> {
+    /// Storage for `capacity` items. Mapped storage has slots up to a whole number of pages,
+    /// but ring still holds no more than `capacity` items.
+    pub fn with_capacity(capacity: usize) -> Self {
+        #[cfg(all(target_os = "linux", not(miri)))]
+        {
+            if let Some((ptr, slots)) = linux::map::<T>(capacity) {
+                return MirroredStorage {
+                    inner: Inner::Mapped { ptr, slots },
+                    capacity,
+                };
+            }
+        }
+        let mut storage = Vec::with_capacity(capacity);
+        storage.resize_with(capacity, MaybeUninit::uninit);
+        MirroredStorage {
+            inner: Inner::Heap(storage),
+            capacity,
+        }
+    }
+
+    /// Whether storage is actually mirrored rather than a heap fallback.
+    pub fn is_mirrored(&self) -> bool {
+        match self.inner {
+            #[cfg(all(target_os = "linux", not(miri)))]
+            Inner::Mapped { .. } => true,
+            Inner::Heap(_) => false,
+        }
+    }
+
+    // `len` slots starting at `start` as one slice, if storage is mirrored.
+    #[cfg_attr(any(not(target_os = "linux"), miri), allow(unused_variables))]
+    fn mirrored(&self, start: usize, len: usize) -> Option<&[MaybeUninit<T>]> {
+        match self.inner {
+            #[cfg(all(target_os = "linux", not(miri)))]
+            Inner::Mapped { ptr, slots } => {
+                debug_assert!(start < slots && len <= slots);
+                Some(unsafe { slice::from_raw_parts(ptr.add(start), len) })
+            }
+            Inner::Heap(_) => None,
+        }
+    }
+}
+
+unsafe impl<T> Storage<T> for MirroredStorage<T> {
+    #[inline]
+    fn slots(&self) -> &[MaybeUninit<T>] {
+        match self.inner {
+            #[cfg(all(target_os = "linux", not(miri)))]
+            Inner::Mapped { ptr, slots } => unsafe { slice::from_raw_parts(ptr, slots) },
+            Inner::Heap(ref slots) => slots,
+        }
+    }
+
+    #[inline]
+    fn slots_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        match self.inner {
+            #[cfg(all(target_os = "linux", not(miri)))]
+            Inner::Mapped { ptr, slots } => unsafe { slice::from_raw_parts_mut(ptr, slots) },
+            Inner::Heap(ref mut slots) => slots,
+        }
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl<T> Drop for MirroredStorage<T> {
+    fn drop(&mut self) {
+        #[cfg(all(target_os = "linux", not(miri)))]
+        {
+            if let Inner::Mapped { ptr, slots } = self.inner {
+                linux::unmap(ptr, slots);
+            }
+        }
+    }
+}
+
+impl<T> Hoop<T, MirroredStorage<T>> {
+    /// Create new mirrored ring buffer that holds up to `capacity` items, just like
+    /// [`Hoop::with_capacity`] does.
+    pub fn mirrored(capacity: usize) -> Self {
+        Hoop::from_storage(MirroredStorage::with_capacity(capacity))
+    }
+
+    /// Whether items are kept in mirrored storage, so [`Hoop::as_slice`] never moves them.
+    pub fn is_mirrored(&self) -> bool {
+        self.storage.is_mirrored()
+    }
+
+    /// Every item as one slice, from the oldest to the newest. Mirrored storage never moves or
+    /// copies anything, heap fallback rotates items in place like [`Hoop::make_contiguous`]
+    /// when they wrap around.
+    pub fn as_slice(&mut self) -> &[T] {
+        if self.is_mirrored() {
+            // Mirrored items are always contiguous.
+            return self.contiguous().unwrap_or_default();
+        }
+        self.make_contiguous()
+    }
+
+    /// Every item as one slice without moving anything, which always works for mirrored
+    /// storage. Heap fallback only has one when items don't wrap around.
+    pub fn contiguous(&self) -> Option<&[T]> {
+        if let Some(slots) = self.storage.mirrored(self.read_position, self.len) {
+            // Slots past the end of storage are the ones at its start, which are initialized.
+            return Some(unsafe { slice::from_raw_parts(slots.as_ptr() as *const T, slots.len()) });
+        }
+        match self.as_slices() {
+            (items, &[]) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", not(miri)))]
+mod linux {
+    use core::mem::{align_of, size_of, MaybeUninit};
+    use core::ptr;
+
+    use libc;
+
+    fn gcd(mut a: usize, mut b: usize) -> usize {
+        while b != 0 {
+            let rest = a % b;
+            a = b;
+            b = rest;
+        }
+        a
+    }
+
+    // Map a `memfd` twice in a row. Returns first slot and number of slots, which is rounded up
+    // so that storage spans whole pages.
+    pub(super) fn map<T>(capacity: usize) -> Option<(*mut MaybeUninit<T>, usize)> {
+        let size = size_of::<T>();
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if capacity == 0 || size == 0 || page <= 0 {
+            return None;
+        }
+        let page = page as usize;
+        if align_of::<T>() > page {
+            return None;
+        }
+        // Smallest size that is a multiple of both page and item sizes.
+        let unit = (page / gcd(page, size)).checked_mul(size)?;
+        let bytes = capacity.checked_mul(size)?.checked_add(unit - 1)? / unit * unit;
+        let mapped = bytes.checked_mul(2)?;
+        if mapped > isize::MAX as usize {
+            return None;
+        }
+        unsafe {
+            let fd =
+                libc::memfd_create(b"hoop\0".as_ptr() as *const libc::c_char, libc::MFD_CLOEXEC);
+            if fd < 0 {
+                return None;
+            }
+            let ptr = map_fd(fd, bytes, mapped);
+            libc::close(fd);
+            ptr.map(|ptr| (ptr as *mut MaybeUninit<T>, bytes / size))
+        }
+    }
+
+    unsafe fn map_fd(fd: libc::c_int, bytes: usize, mapped: usize) -> Option<*mut u8> {
+        if libc::ftruncate(fd, bytes as libc::off_t) != 0 {
+            return None;
+        }
+        // Reserve address space for both halves first, so nothing else can end up in between.
+        let base = libc::mmap(
+            ptr::null_mut(),
+            mapped,
+            libc::PROT_NONE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        if base == libc::MAP_FAILED {
+            return None;
+        }
+        let base = base as *mut u8;
+        for half in &[base, base.add(bytes)] {
+            let ptr = libc::mmap(
+                *half as *mut libc::c_void,
+                bytes,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_FIXED,
+                fd,
+                0,
+            );
+            if ptr == libc::MAP_FAILED {
+                libc::munmap(base as *mut libc::c_void, mapped);
+                return None;
+            }
+        }
+        Some(base)
+    }
+
+    pub(super) fn unmap<T>(ptr: *mut MaybeUninit<T>, slots: usize) {
+        unsafe {
+            libc::munmap(ptr as *mut libc::c_void, 2 * slots * size_of::<T>());
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(unused_must_use)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    fn exercise(mut buffer: MirroredHoop<usize>) {
+        let capacity = buffer.capacity();
+        for i in 0..capacity + capacity / 2 {
+            buffer.overwrite(i);
+            let expected: Vec<usize> = buffer.iter().cloned().collect();
+            assert_eq!(&expected[..], buffer.as_slice());
+        }
+        for _ in 0..capacity / 3 {
+            buffer.pop();
+        }
+        let expected: Vec<usize> = buffer.iter().cloned().collect();
+        assert_eq!(&expected[..], buffer.as_slice());
+    }
+
+    #[test]
+    fn as_slice_is_contiguous_after_wraparound() {
+        exercise(MirroredHoop::mirrored(10));
+        exercise(MirroredHoop::mirrored(64));
+        let heap = Hoop::from_storage(MirroredStorage {
+            inner: Inner::Heap((0..7).map(|_| MaybeUninit::uninit()).collect()),
+            capacity: 7,
+        });
+        assert!(!heap.is_mirrored());
+        exercise(heap);
+    }
+
+    #[cfg(all(target_os = "linux", not(miri)))]
+    #[test]
+    fn mapped_slots_span_pages_but_capacity_is_kept() {
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let mut buffer = MirroredHoop::<[u8; 3]>::mirrored(5);
+        assert!(buffer.is_mirrored());
+        assert_eq!(5, buffer.capacity());
+        let slots = buffer.storage.slots().len();
+        assert!(slots > 5);
+        assert_eq!(0, (slots * 3) % page);
+        // Keep going past the end of slots, so that items wrap around mapping.
+        for i in 0..slots + 3 {
+            buffer.overwrite([i as u8; 3]);
+            assert!(buffer.len() <= 5);
+        }
+        let newest: Vec<u8> = (slots - 2..slots + 3).map(|i| i as u8).collect();
+        let items: Vec<u8> = buffer
+            .contiguous()
+            .unwrap()
+            .iter()
+            .map(|item| item[0])
+            .collect();
+        assert_eq!(newest, items);
+    }
+
+    #[test]
+    fn every_operation_respects_requested_capacity() {
+        use std::collections::VecDeque;
+
+        let mut buffer = MirroredHoop::mirrored(5);
+        let mut model = VecDeque::new();
+        let slots = buffer.storage.slots().len();
+        for i in 0..3 * slots {
+            match i % 7 {
+                0..=2 => {
+                    buffer.overwrite(i);
+                    if model.len() == 5 {
+                        model.pop_front();
+                    }
+                    model.push_back(i);
+                }
+                3 => {
+                    buffer.overwrite_front(i);
+                    if model.len() == 5 {
+                        model.pop_back();
+                    }
+                    model.push_front(i);
+                }
+                4 => assert_eq!(model.pop_back(), buffer.pop_back()),
+                5 => {
+                    if buffer.write(i) == ::WriteResult::Done {
+                        model.push_back(i);
+                    }
+                }
+                _ => assert_eq!(model.pop_front(), buffer.pop()),
+            }
+            let expected: Vec<usize> = model.iter().cloned().collect();
+            assert_eq!(&expected[..], buffer.as_slice());
+        }
+    }
+
+    #[test]
+    fn contiguous_through_shared_reference() {
+        let mut buffer = MirroredHoop::mirrored(4);
+        for i in 0..6 {
+            buffer.overwrite(i);
+        }
+        let buffer = &buffer;
+        if buffer.is_mirrored() {
+            assert_eq!(Some(&[2, 3, 4, 5][..]), buffer.contiguous());
+        }
+        let mut heap = Hoop::from_storage(MirroredStorage {
+            inner: Inner::Heap((0..4).map(|_| MaybeUninit::uninit()).collect()),
+            capacity: 4,
+        });
+        heap.extend(0..3);
+        assert_eq!(Some(&[0, 1, 2][..]), heap.contiguous());
+        heap.extend(3..6);
+        assert_eq!(None, heap.contiguous());
+        assert_eq!(&[2, 3, 4, 5], heap.as_slice());
+    }
+
+    #[test]
+    fn zero_capacity_and_zero_sized_items_fall_back() {
+        let mut empty = MirroredHoop::<u32>::mirrored(0);
+        assert!(!empty.is_mirrored());
+        assert_eq!(&[] as &[u32], empty.as_slice());
+        let mut units = MirroredHoop::mirrored(3);
+        assert!(!units.is_mirrored());
+        units.write(());
+        assert_eq!(&[()], units.as_slice());
+    }
+
+    #[test]
+    fn items_are_dropped_once() {
+        let token = Rc::new(());
+        {
+            let mut buffer = MirroredHoop::mirrored(4);
+            for _ in 0..buffer.capacity() + 3 {
+                buffer.overwrite(Rc::clone(&token));
+            }
+            buffer.as_slice();
+        }
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+}
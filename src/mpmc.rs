@@ -0,0 +1,345 @@
+//! Bounded multi-producer/multi-consumer ring.
+//!
+//! [`Sender`] and [`Receiver`] are cheap to clone and share one [`Hoop`] behind a lock. Once
+//! every `Sender` is dropped the ring is disconnected and receivers get
+//! [`PopError::Disconnected`] as soon as it's drained.
+//!
+//! ```
+//! use hoop::mpmc;
+//! use std::thread;
+//!
+//! let (sender, receiver) = mpmc::channel(4);
+//! let workers: Vec<_> = (0..4)
+//!     .map(|worker| {
+//!         let sender = sender.clone();
+//!         thread::spawn(move || {
+//!             for i in 0..10 {
+//!                 sender.write_blocking(worker * 10 + i, None);
+//!             }
+//!         })
+//!     })
+//!     .collect();
+//! drop(sender);
+//! let mut received = Vec::new();
+//! while let Ok(item) = receiver.pop_blocking(None) {
+//!     received.push(item);
+//! }
+//! for worker in workers {
+//!     worker.join().unwrap();
+//! }
+//! received.sort();
+//! assert_eq!((0..40).collect::<Vec<_>>(), received);
+//! ```
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use sync::lock;
+use {Hoop, WriteResult};
+
+struct State<T> {
+    hoop: Hoop<T>,
+    senders: usize,
+    receivers: usize,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    // Signalled when an item is written or the last sender is gone.
+    not_empty: Condvar,
+    // Signalled when an item is popped or the last receiver is gone.
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+    // Wait on `condvar` until `deadline`. Returns `None` once it passed.
+    fn wait<'a>(
+        &self,
+        condvar: &Condvar,
+        guard: MutexGuard<'a, State<T>>,
+        deadline: Option<Instant>,
+    ) -> Option<MutexGuard<'a, State<T>>> {
+        match deadline {
+            None => Some(condvar.wait(guard).unwrap_or_else(|e| e.into_inner())),
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return None;
+                }
+                let (guard, _) = condvar
+                    .wait_timeout(guard, deadline - now)
+                    .unwrap_or_else(|e| e.into_inner());
+                Some(guard)
+            }
+        }
+    }
+}
+
+/// Create a bounded ring with desired capacity and return its first sender and receiver.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            hoop: Hoop::with_capacity(capacity),
+            senders: 1,
+            receivers: 1,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+/// Writing handle of a multi-producer/multi-consumer ring.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Try writting to a ring without waiting.
+    pub fn try_write(&self, item: T) -> WriteResult {
+        let result = lock(&self.shared.state).hoop.write(item);
+        if result == WriteResult::Done {
+            self.shared.not_empty.notify_one();
+        }
+        result
+    }
+
+    /// Write to a ring, waiting for a free slot for at most `timeout` or forever if it's `None`.
+    ///
+    /// Returns `WriteResult::TooMany` if time ran out or every [`Receiver`] is gone while the
+    /// ring is full. Ring with zero capacity never has a free slot, so it doesn't wait at all.
+    pub fn write_blocking(&self, item: T, timeout: Option<Duration>) -> WriteResult {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = lock(&self.shared.state);
+        while state.hoop.is_full() && state.receivers > 0 && state.hoop.capacity() > 0 {
+            state = match self.shared.wait(&self.shared.not_full, state, deadline) {
+                Some(state) => state,
+                None => return WriteResult::TooMany,
+            };
+        }
+        let result = state.hoop.write(item);
+        drop(state);
+        if result == WriteResult::Done {
+            self.shared.not_empty.notify_one();
+        }
+        result
+    }
+
+    /// Write even if at a capacity, evicting the oldest item.
+    pub fn overwrite(&self, item: T) {
+        lock(&self.shared.state).hoop.overwrite(item);
+        self.shared.not_empty.notify_one();
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        lock(&self.shared.state).senders += 1;
+        Sender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared.state);
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+/// Reading handle of a multi-producer/multi-consumer ring.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Pop oldest item from a ring without waiting.
+    pub fn pop(&self) -> Option<T> {
+        let item = lock(&self.shared.state).hoop.pop();
+        if item.is_some() {
+            self.shared.not_full.notify_one();
+        }
+        item
+    }
+
+    /// Pop oldest item from a ring, waiting for one for at most `timeout` or forever if it's
+    /// `None`.
+    pub fn pop_blocking(&self, timeout: Option<Duration>) -> Result<T, PopError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = lock(&self.shared.state);
+        loop {
+            if let Some(item) = state.hoop.pop() {
+                drop(state);
+                self.shared.not_full.notify_one();
+                return Ok(item);
+            }
+            if state.senders == 0 {
+                return Err(PopError::Disconnected);
+            }
+            state = match self.shared.wait(&self.shared.not_empty, state, deadline) {
+                Some(state) => state,
+                None => return Err(PopError::Timeout),
+            };
+        }
+    }
+
+    /// Whether every [`Sender`] is gone. Items written before that can still be popped.
+    pub fn is_disconnected(&self) -> bool {
+        lock(&self.shared.state).senders == 0
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Receiver<T> {
+        lock(&self.shared.state).receivers += 1;
+        Receiver {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared.state);
+        state.receivers -= 1;
+        if state.receivers == 0 {
+            drop(state);
+            self.shared.not_full.notify_all();
+        }
+    }
+}
+
+/// Reason a blocking pop came back empty-handed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PopError {
+    /// Nothing was written before timeout.
+    Timeout,
+    /// Ring is empty and every sender is gone.
+    Disconnected,
+}
+
+#[cfg(test)]
+#[allow(unused_must_use)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+    use std::thread;
+
+    #[test]
+    fn try_write_and_pop() {
+        let (sender, receiver) = channel(2);
+        assert_eq!(WriteResult::Done, sender.try_write('1'));
+        assert_eq!(WriteResult::Done, sender.try_write('2'));
+        assert_eq!(WriteResult::TooMany, sender.try_write('3'));
+        assert_eq!(Some('1'), receiver.pop());
+        sender.overwrite('3');
+        sender.overwrite('4');
+        assert_eq!(Some('3'), receiver.pop());
+        assert_eq!(Some('4'), receiver.pop());
+        assert_eq!(None, receiver.pop());
+    }
+
+    #[test]
+    fn blocking_timeouts() {
+        let (sender, receiver) = channel(1);
+        let timeout = Some(Duration::from_millis(10));
+        assert_eq!(Err(PopError::Timeout), receiver.pop_blocking(timeout));
+        assert_eq!(WriteResult::Done, sender.write_blocking('1', timeout));
+        assert_eq!(WriteResult::TooMany, sender.write_blocking('2', timeout));
+        assert_eq!(Ok('1'), receiver.pop_blocking(timeout));
+    }
+
+    #[test]
+    fn zero_capacity_never_waits() {
+        let (sender, receiver) = channel(0);
+        assert_eq!(WriteResult::TooMany, sender.write_blocking('1', None));
+        assert_eq!(
+            Err(PopError::Timeout),
+            receiver.pop_blocking(Some(Duration::from_millis(1)))
+        );
+    }
+
+    #[test]
+    fn disconnect_after_drain() {
+        let (sender, receiver) = channel(2);
+        let other = sender.clone();
+        sender.try_write('1');
+        drop(sender);
+        assert!(!receiver.is_disconnected());
+        other.try_write('2');
+        drop(other);
+        assert!(receiver.is_disconnected());
+        assert_eq!(Ok('1'), receiver.pop_blocking(None));
+        assert_eq!(Ok('2'), receiver.pop_blocking(None));
+        assert_eq!(Err(PopError::Disconnected), receiver.pop_blocking(None));
+    }
+
+    #[test]
+    fn blocked_writer_gives_up_without_receivers() {
+        let (sender, receiver) = channel(1);
+        sender.try_write('1');
+        let writer = thread::spawn(move || sender.write_blocking('2', None));
+        thread::sleep(Duration::from_millis(10));
+        drop(receiver);
+        assert_eq!(WriteResult::TooMany, writer.join().unwrap());
+    }
+
+    #[test]
+    fn wakes_blocked_reader() {
+        let (sender, receiver) = channel(1);
+        let reader = thread::spawn(move || receiver.pop_blocking(None));
+        thread::sleep(Duration::from_millis(10));
+        sender.try_write('1');
+        assert_eq!(Ok('1'), reader.join().unwrap());
+    }
+
+    #[test]
+    fn many_writers_many_readers() {
+        let (sender, receiver) = channel(3);
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    for i in 0..250 {
+                        assert_eq!(
+                            WriteResult::Done,
+                            sender.write_blocking(writer * 250 + i, None)
+                        );
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || {
+                    let mut received = Vec::new();
+                    while let Ok(item) = receiver.pop_blocking(None) {
+                        received.push(item);
+                    }
+                    received
+                })
+            })
+            .collect();
+        drop(receiver);
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let mut received: Vec<usize> = readers
+            .into_iter()
+            .flat_map(|reader| reader.join().unwrap())
+            .collect();
+        received.sort();
+        assert_eq!((0..1000).collect::<Vec<_>>(), received);
+    }
+}
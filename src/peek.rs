@@ -0,0 +1,81 @@
+use core::ops::{Deref, DerefMut};
+
+use {Hoop, Storage};
+
+/// Mutable access to the oldest item in a [`Hoop`], created by [`Hoop::peek_mut`]. The item can
+/// be popped through the guard once it has been looked at.
+pub struct PeekMut<'data, T: 'data, S: 'data + Storage<T>> {
+    // Never empty while guard is alive.
+    hoop: &'data mut Hoop<T, S>,
+}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> PeekMut<'data, T, S> {
+    pub(crate) fn new(hoop: &'data mut Hoop<T, S>) -> Option<Self> {
+        if hoop.is_empty() {
+            None
+        } else {
+            Some(PeekMut { hoop })
+        }
+    }
+
+    /// Pop the peeked item from a buffer.
+    pub fn pop(this: PeekMut<'data, T, S>) -> T {
+        match this.hoop.pop() {
+            Some(item) => item,
+            None => unreachable!("peeked buffer is empty"),
+        }
+    }
+}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> Deref for PeekMut<'data, T, S> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        match self.hoop.front() {
+            Some(item) => item,
+            None => unreachable!("peeked buffer is empty"),
+        }
+    }
+}
+
+impl<'data, T: 'data, S: 'data + Storage<T>> DerefMut for PeekMut<'data, T, S> {
+    fn deref_mut(&mut self) -> &mut T {
+        match self.hoop.front_mut() {
+            Some(item) => item,
+            None => unreachable!("peeked buffer is empty"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ArrayHoop;
+
+    #[test]
+    fn peek_mut_modifies_in_place() {
+        let mut buffer: ArrayHoop<i32, 2> = ArrayHoop::new();
+        assert!(buffer.peek_mut().is_none());
+        buffer.overwrite(1);
+        buffer.overwrite(2);
+        buffer.overwrite(3);
+        *buffer.peek_mut().unwrap() += 10;
+        assert_eq!(Some(&12), buffer.front());
+        assert_eq!(2, buffer.len());
+    }
+
+    #[test]
+    fn peek_mut_pops_on_demand() {
+        let mut buffer: ArrayHoop<i32, 3> = ArrayHoop::new();
+        for i in 0..3 {
+            buffer.overwrite(i);
+        }
+        while let Some(peeked) = buffer.peek_mut() {
+            if *peeked >= 2 {
+                break;
+            }
+            PeekMut::pop(peeked);
+        }
+        assert_eq!(Some(&2), buffer.front());
+        assert_eq!(1, buffer.len());
+    }
+}
@@ -0,0 +1,469 @@
+//! Ring of variable-length byte records kept back-to-back in one arena.
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::{DoubleEndedIterator, ExactSizeIterator, FusedIterator, Iterator};
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use {assume_init_slice, Storage};
+
+// Every record is framed by its length, stored as `u32` both before and after it, so records
+// can be walked from either end.
+const TAG: usize = 4;
+
+/// Ring of byte records of any length in a single arena of `capacity` bytes. Each record takes
+/// 8 extra bytes for its framing and never wraps around the end of the arena, so it's always
+/// handed out as one slice.
+///
+/// Arena lives in `S`, which is a heap allocated `Vec` unless stated otherwise. Without `alloc`
+/// feature there is no default, see [`ArrayRecordHoop`].
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use hoop::RecordHoop;
+///
+/// let mut log = RecordHoop::with_capacity(32);
+/// log.push_record(b"first").unwrap();
+/// log.push_record(b"second").unwrap();
+/// assert!(log.push_record(b"third").is_err());
+/// assert_eq!(Ok(1), log.push_record_overwrite(b"third"));
+/// let mut records = log.iter();
+/// assert_eq!(Some(&b"second"[..]), records.next());
+/// assert_eq!(Some(&b"third"[..]), records.next_back());
+/// assert_eq!(None, records.next());
+/// # }
+/// ```
+pub struct RecordHoop<
+    #[cfg(feature = "alloc")] S: Storage<u8> = Vec<MaybeUninit<u8>>,
+    #[cfg(not(feature = "alloc"))] S: Storage<u8>,
+> {
+    // Bytes in `head..end` and, once wrapped around, in `0..tail` are initialized frames. Bytes
+    // after `end` are left unused when a frame doesn't fit there.
+    storage: S,
+    head: usize,
+    end: usize,
+    // Zero unless wrapped, wrapped part is never empty.
+    tail: usize,
+    // Number of records.
+    len: usize,
+}
+
+/// [`RecordHoop`] that keeps its arena inline, without allocating.
+pub type ArrayRecordHoop<const N: usize> = RecordHoop<[MaybeUninit<u8>; N]>;
+
+/// Reason a record wasn't pushed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RecordError {
+    /// Not enough room right now.
+    Full,
+    /// Record won't fit even in an empty ring.
+    TooLarge,
+}
+
+#[cfg(feature = "alloc")]
+impl RecordHoop {
+    /// Create new record ring with an arena of `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> RecordHoop {
+        let mut storage = Vec::with_capacity(capacity);
+        storage.resize_with(capacity, MaybeUninit::uninit);
+        RecordHoop::from_storage(storage)
+    }
+}
+
+impl<const N: usize> RecordHoop<[MaybeUninit<u8>; N]> {
+    /// Create new record ring with an arena of `N` bytes.
+    pub const fn new() -> Self {
+        RecordHoop::from_storage([MaybeUninit::uninit(); N])
+    }
+}
+
+impl<const N: usize> Default for RecordHoop<[MaybeUninit<u8>; N]> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Storage<u8>> RecordHoop<S> {
+    const fn from_storage(storage: S) -> RecordHoop<S> {
+        RecordHoop {
+            storage,
+            head: 0,
+            end: 0,
+            tail: 0,
+            len: 0,
+        }
+    }
+
+    /// Size of the arena in bytes.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.storage.capacity()
+    }
+
+    /// Number of records in a ring.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether ring holds no records.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append `record` as the newest one, failing if there isn't enough room for it.
+    pub fn push_record(&mut self, record: &[u8]) -> Result<(), RecordError> {
+        if record.len() > u32::MAX as usize || record.len() + 2 * TAG > self.capacity() {
+            return Err(RecordError::TooLarge);
+        }
+        match self.reserve(record.len() + 2 * TAG) {
+            Some(start) => {
+                self.put(start, record);
+                Ok(())
+            }
+            None => Err(RecordError::Full),
+        }
+    }
+
+    /// Append `record` as the newest one, evicting as many of the oldest records as it takes to
+    /// make room. Returns number of evicted records.
+    pub fn push_record_overwrite(&mut self, record: &[u8]) -> Result<usize, RecordError> {
+        let mut evicted = 0;
+        loop {
+            match self.push_record(record) {
+                Err(RecordError::Full) => {
+                    self.pop_record();
+                    evicted += 1;
+                }
+                Ok(()) => return Ok(evicted),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Remove the oldest record, returning its length.
+    pub fn pop_record(&mut self) -> Option<usize> {
+        let record = self.front()?.len();
+        self.len -= 1;
+        self.head += record + 2 * TAG;
+        if self.len == 0 {
+            self.clear();
+        } else if self.head == self.end {
+            self.head = 0;
+            self.end = self.tail;
+            self.tail = 0;
+        }
+        Some(record)
+    }
+
+    /// The oldest record.
+    pub fn front(&self) -> Option<&[u8]> {
+        self.iter().next()
+    }
+
+    /// The newest record.
+    pub fn back(&self) -> Option<&[u8]> {
+        self.iter().next_back()
+    }
+
+    /// Remove every record.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.end = 0;
+        self.tail = 0;
+        self.len = 0;
+    }
+
+    /// Iterate over records, from the oldest to the newest or the other way around.
+    pub fn iter(&self) -> Records<'_> {
+        let slots = self.storage.slots();
+        // Both parts hold nothing but frames, which are initialized.
+        unsafe {
+            Records {
+                front: assume_init_slice(&slots[self.head..self.end]),
+                back: assume_init_slice(&slots[..self.tail]),
+                len: self.len,
+            }
+        }
+    }
+
+    // Find room for a frame of `size` bytes, returning where it starts.
+    fn reserve(&mut self, size: usize) -> Option<usize> {
+        let start = if self.len == 0 {
+            self.clear();
+            0
+        } else if self.tail == 0 && self.end + size <= self.capacity() {
+            self.end
+        } else if self.tail + size <= self.head {
+            self.tail
+        } else {
+            return None;
+        };
+        if start == self.end {
+            self.end += size;
+        } else {
+            self.tail += size;
+        }
+        self.len += 1;
+        Some(start)
+    }
+
+    fn put(&mut self, start: usize, record: &[u8]) {
+        let tag = (record.len() as u32).to_le_bytes();
+        let frame = &mut self.storage.slots_mut()[start..start + record.len() + 2 * TAG];
+        let (header, rest) = frame.split_at_mut(TAG);
+        let (body, trailer) = rest.split_at_mut(record.len());
+        for (slots, bytes) in [(header, &tag[..]), (body, record), (trailer, &tag[..])] {
+            unsafe {
+                ptr::copy_nonoverlapping(bytes.as_ptr(), slots.as_mut_ptr() as *mut u8, bytes.len())
+            };
+        }
+    }
+}
+
+impl<'data, S: Storage<u8>> IntoIterator for &'data RecordHoop<S> {
+    type Item = &'data [u8];
+    type IntoIter = Records<'data>;
+
+    fn into_iter(self) -> Records<'data> {
+        self.iter()
+    }
+}
+
+/// Non-consuming iterator created by [`RecordHoop::iter`].
+pub struct Records<'data> {
+    // Frames not yielded yet, before and after wraparound.
+    front: &'data [u8],
+    back: &'data [u8],
+    len: usize,
+}
+
+fn tag(bytes: &[u8]) -> usize {
+    let mut tag = [0; TAG];
+    tag.copy_from_slice(bytes);
+    u32::from_le_bytes(tag) as usize
+}
+
+impl<'data> Iterator for Records<'data> {
+    type Item = &'data [u8];
+    fn next(&mut self) -> Option<&'data [u8]> {
+        if self.len == 0 {
+            return None;
+        }
+        let frames = if self.front.is_empty() {
+            &mut self.back
+        } else {
+            &mut self.front
+        };
+        let len = tag(&frames[..TAG]);
+        let record = &frames[TAG..TAG + len];
+        *frames = &frames[len + 2 * TAG..];
+        self.len -= 1;
+        Some(record)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'data> DoubleEndedIterator for Records<'data> {
+    fn next_back(&mut self) -> Option<&'data [u8]> {
+        if self.len == 0 {
+            return None;
+        }
+        let frames = if self.back.is_empty() {
+            &mut self.front
+        } else {
+            &mut self.back
+        };
+        let end = frames.len();
+        let len = tag(&frames[end - TAG..]);
+        let record = &frames[end - TAG - len..end - TAG];
+        *frames = &frames[..end - len - 2 * TAG];
+        self.len -= 1;
+        Some(record)
+    }
+}
+
+impl<'data> ExactSizeIterator for Records<'data> {}
+
+impl<'data> FusedIterator for Records<'data> {}
+
+#[cfg(test)]
+#[allow(unused_must_use)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn collect(log: &ArrayRecordHoop<32>) -> Vec<&[u8]> {
+        log.iter().collect()
+    }
+
+    #[test]
+    fn strict_push_fails_when_full() {
+        let mut log = ArrayRecordHoop::<32>::new();
+        assert_eq!(Ok(()), log.push_record(b"abcd"));
+        assert_eq!(Ok(()), log.push_record(b""));
+        assert_eq!(Ok(()), log.push_record(b"ef"));
+        assert_eq!(Err(RecordError::Full), log.push_record(b""));
+        assert_eq!(Err(RecordError::TooLarge), log.push_record(&[0; 25]));
+        assert_eq!(vec![&b"abcd"[..], b"", b"ef"], collect(&log));
+    }
+
+    #[test]
+    fn overwrite_evicts_oldest_and_wraps() {
+        let mut log = ArrayRecordHoop::<32>::new();
+        log.push_record(b"a");
+        log.push_record(b"bcdefghij");
+        // 3 bytes are left at the end, new frame goes to the start once `a` is gone.
+        assert_eq!(Ok(1), log.push_record_overwrite(b"k"));
+        assert_eq!(vec![&b"bcdefghij"[..], b"k"], collect(&log));
+        // Evicting `bcdefghij` frees the end of the arena again.
+        assert_eq!(Ok(1), log.push_record_overwrite(b""));
+        assert_eq!(Ok(0), log.push_record_overwrite(b"lmnop"));
+        assert_eq!(vec![&b"k"[..], b"", b"lmnop"], collect(&log));
+        assert_eq!(Ok(3), log.push_record_overwrite(&[7; 24]));
+        assert_eq!(vec![&[7; 24][..]], collect(&log));
+        assert_eq!(
+            Err(RecordError::TooLarge),
+            log.push_record_overwrite(&[0; 25])
+        );
+    }
+
+    // More slots than a ring may use, just like mirrored storage rounded up to whole pages.
+    struct Padded([MaybeUninit<u8>; 32]);
+
+    unsafe impl Storage<u8> for Padded {
+        fn slots(&self) -> &[MaybeUninit<u8>] {
+            &self.0
+        }
+
+        fn slots_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+            &mut self.0
+        }
+
+        fn capacity(&self) -> usize {
+            16
+        }
+    }
+
+    #[test]
+    fn arena_ends_at_storage_capacity() {
+        let mut log = RecordHoop::from_storage(Padded([MaybeUninit::uninit(); 32]));
+        assert_eq!(16, log.capacity());
+        assert_eq!(Err(RecordError::TooLarge), log.push_record(&[0; 9]));
+        assert_eq!(Ok(()), log.push_record(&[1; 8]));
+        assert_eq!(Err(RecordError::Full), log.push_record(b""));
+        assert_eq!(Ok(1), log.push_record_overwrite(b"ab"));
+        assert_eq!(Some(&b"ab"[..]), log.front());
+    }
+
+    #[test]
+    fn iterates_both_ways_across_wraparound() {
+        let mut log = ArrayRecordHoop::<40>::new();
+        for record in [&b"one"[..], b"two", b"three"] {
+            log.push_record(record).unwrap();
+        }
+        assert_eq!(Some(3), log.pop_record());
+        log.push_record(b"4").unwrap();
+        let mut records = log.iter();
+        assert_eq!(3, records.len());
+        assert_eq!(Some(&b"4"[..]), records.next_back());
+        assert_eq!(Some(&b"two"[..]), records.next());
+        assert_eq!(Some(&b"three"[..]), records.next_back());
+        assert_eq!(None, records.next());
+        assert_eq!(None, records.next_back());
+        let backward: Vec<&[u8]> = log.iter().rev().collect();
+        assert_eq!(vec![&b"4"[..], b"three", b"two"], backward);
+    }
+
+    #[test]
+    fn pop_record_unwraps() {
+        let mut log = ArrayRecordHoop::<32>::new();
+        log.push_record(b"abcdefgh");
+        log.push_record(b"ij");
+        log.push_record_overwrite(b"klm");
+        assert_eq!(Some(&b"ij"[..]), log.front());
+        assert_eq!(Some(&b"klm"[..]), log.back());
+        assert_eq!(Some(2), log.pop_record());
+        // Room after `klm` is contiguous again.
+        assert_eq!(Ok(()), log.push_record(&[1; 13]));
+        assert_eq!(Some(3), log.pop_record());
+        assert_eq!(Some(13), log.pop_record());
+        assert_eq!(None, log.pop_record());
+        assert!(log.is_empty());
+        assert_eq!(Ok(()), log.push_record(&[2; 24]));
+    }
+
+    #[test]
+    fn zero_capacity_takes_nothing() {
+        let mut log = ArrayRecordHoop::<0>::new();
+        assert_eq!(Err(RecordError::TooLarge), log.push_record(b""));
+        assert_eq!(Err(RecordError::TooLarge), log.push_record_overwrite(b""));
+        assert_eq!(None, log.front());
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+#[allow(unused_must_use)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Push(Vec<u8>),
+        PushOverwrite(Vec<u8>),
+        Pop,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        let record = || prop::collection::vec(any::<u8>(), 0..16);
+        prop_oneof![
+            record().prop_map(Op::Push),
+            record().prop_map(Op::PushOverwrite),
+            Just(Op::Pop),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn records_agree_with_model(capacity in 0..64usize, ops in prop::collection::vec(op(), 0..64)) {
+            let mut log = RecordHoop::with_capacity(capacity);
+            let mut model: VecDeque<Vec<u8>> = VecDeque::new();
+            for op in ops {
+                match op {
+                    Op::Push(record) => match log.push_record(&record) {
+                        Ok(()) => model.push_back(record),
+                        Err(RecordError::Full) => prop_assert!(!model.is_empty()),
+                        Err(RecordError::TooLarge) => prop_assert!(record.len() + 8 > capacity),
+                    },
+                    Op::PushOverwrite(record) => match log.push_record_overwrite(&record) {
+                        Ok(evicted) => {
+                            model.drain(..evicted);
+                            model.push_back(record);
+                        }
+                        Err(err) => {
+                            prop_assert_eq!(RecordError::TooLarge, err);
+                            prop_assert!(record.len() + 8 > capacity);
+                        }
+                    },
+                    Op::Pop => {
+                        prop_assert_eq!(model.pop_front().map(|record| record.len()), log.pop_record());
+                    }
+                }
+                prop_assert_eq!(model.len(), log.len());
+                let forward: Vec<&[u8]> = log.iter().collect();
+                let expected: Vec<&[u8]> = model.iter().map(|record| &record[..]).collect();
+                prop_assert_eq!(&expected, &forward);
+                let backward: Vec<&[u8]> = log.iter().rev().collect();
+                let expected: Vec<&[u8]> = model.iter().rev().map(|record| &record[..]).collect();
+                prop_assert_eq!(expected, backward);
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use core::iter::DoubleEndedIterator;
+
+use {Hoop, Iter, Storage, WriteResult};
+
+/// Operations shared by every ring buffer in this crate, whatever it keeps its items in.
+///
+/// ```
+/// use hoop::{ArrayHoop, RingBuffer};
+///
+/// fn sum_of_last<R: RingBuffer<u32>>(ring: &R, n: usize) -> u32 {
+///     ring.iter().rev().take(n).sum()
+/// }
+///
+/// let mut small: ArrayHoop<u32, 4> = ArrayHoop::new();
+/// let mut large: ArrayHoop<u32, 8> = ArrayHoop::new();
+/// for i in 0..4 {
+///     small.write(i);
+///     large.write(i);
+/// }
+/// assert_eq!(5, sum_of_last(&small, 2));
+/// assert_eq!(6, sum_of_last(&large, 8));
+/// ```
+pub trait RingBuffer<T> {
+    /// Non-consuming iterator from the oldest to the newest item.
+    type Iter<'a>: DoubleEndedIterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    /// Number of items buffer can hold.
+    fn capacity(&self) -> usize;
+
+    /// Number of items in a buffer.
+    fn len(&self) -> usize;
+
+    /// Whether buffer holds no items.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether buffer is at capacity, so next `write` would fail.
+    fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Number of items that can be written before buffer is full.
+    fn free_slots(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    /// Try writting to a buffer.
+    fn write(&mut self, item: T) -> WriteResult;
+
+    /// Try writting to a buffer, handing `item` back if it's full.
+    fn try_write(&mut self, item: T) -> Result<(), T>;
+
+    /// Write even if at a capacity, evicting the oldest item.
+    fn overwrite(&mut self, item: T);
+
+    /// Write even if at a capacity, returning the oldest item if it was evicted.
+    fn push_overwrite(&mut self, item: T) -> Option<T>;
+
+    /// Pop oldest item from a buffer.
+    fn pop(&mut self) -> Option<T>;
+
+    /// Remove every item from a buffer.
+    fn clear(&mut self);
+
+    /// Item `index` positions after the oldest one.
+    fn get(&self, index: usize) -> Option<&T>;
+
+    /// Create non-consuming iterator.
+    fn iter(&self) -> Self::Iter<'_>;
+}
+
+impl<T, S: Storage<T>> RingBuffer<T> for Hoop<T, S> {
+    type Iter<'a>
+        = Iter<'a, T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        Hoop::capacity(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        Hoop::len(self)
+    }
+
+    #[inline]
+    fn write(&mut self, item: T) -> WriteResult {
+        Hoop::write(self, item)
+    }
+
+    #[inline]
+    fn try_write(&mut self, item: T) -> Result<(), T> {
+        Hoop::try_write(self, item)
+    }
+
+    #[inline]
+    fn overwrite(&mut self, item: T) {
+        Hoop::overwrite(self, item)
+    }
+
+    #[inline]
+    fn push_overwrite(&mut self, item: T) -> Option<T> {
+        Hoop::push_overwrite(self, item)
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        Hoop::pop(self)
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        Hoop::clear(self)
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> Option<&T> {
+        Hoop::get(self, index)
+    }
+
+    #[inline]
+    fn iter(&self) -> Iter<'_, T> {
+        Hoop::iter(self)
+    }
+}
+
+#[cfg(test)]
+#[allow(unused_must_use)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+    use ArrayHoop;
+
+    fn exercise<R: RingBuffer<char>>(mut ring: R) {
+        assert_eq!(3, ring.capacity());
+        assert!(ring.is_empty());
+        ring.write('1');
+        ring.write('2');
+        ring.write('3');
+        assert!(ring.is_full());
+        assert_eq!(WriteResult::TooMany, ring.write('4'));
+        assert_eq!(Err('4'), ring.try_write('4'));
+        assert_eq!(Some('1'), ring.push_overwrite('0'));
+        ring.overwrite('4');
+        assert_eq!(Some('3'), ring.pop());
+        assert_eq!(2, ring.len());
+        assert_eq!(Some(&'4'), ring.get(1));
+        let result: Vec<char> = ring.iter().rev().cloned().collect();
+        assert_eq!(vec!['4', '0'], result);
+        ring.clear();
+        assert!(ring.is_empty());
+        assert_eq!(None, ring.pop());
+    }
+
+    #[test]
+    fn array_hoop() {
+        exercise(ArrayHoop::<char, 3>::new());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn heap_hoop() {
+        exercise(Hoop::with_capacity(3));
+    }
+}
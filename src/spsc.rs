@@ -0,0 +1,917 @@
+//! Lock-free single-producer/single-consumer ring.
+//!
+//! Created by [`Hoop::split`](../struct.Hoop.html#method.split). [`Producer`] and [`Consumer`]
+//! can be moved to different threads; neither of them ever takes a lock.
+//!
+//! ```
+//! use hoop::{Hoop, WriteResult};
+//! use std::thread;
+//!
+//! let (mut producer, mut consumer) = Hoop::with_capacity(16).split();
+//! let writer = thread::spawn(move || {
+//!     for i in 0..100 {
+//!         while producer.write(i) == WriteResult::TooMany {
+//!             thread::yield_now();
+//!         }
+//!     }
+//! });
+//! let mut received = Vec::new();
+//! while received.len() < 100 {
+//!     match consumer.pop() {
+//!         Some(i) => received.push(i),
+//!         None => thread::yield_now(),
+//!     }
+//! }
+//! writer.join().unwrap();
+//! assert_eq!((0..100).collect::<Vec<_>>(), received);
+//! ```
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::iter::{DoubleEndedIterator, IntoIterator, Iterator};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+#[cfg(not(loom))]
+use core::ptr;
+
+#[cfg(not(loom))]
+use grant::GrantedSlots;
+use sync::{spin_loop, Arc, AtomicUsize, Ordering, UnsafeCell};
+use {Hoop, Storage, WriteResult};
+
+// Set in `head` while consumer holds a snapshot or a read grant.
+const PINNED: usize = 1;
+
+// Positions are a slot index plus a lap counter in the bits above it, so that a full ring can be
+// told apart from an empty one and a stale position never compares equal to a fresh one.
+struct Shared<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // Smallest power of two above capacity, i.e. the step between laps.
+    one_lap: usize,
+    // Next item to be claimed by either side, shifted left by one to make room for `PINNED`.
+    head: AtomicUsize,
+    // Position right after the last item that is moved out of its slot. Slots behind it are
+    // free.
+    released: AtomicUsize,
+    // Next write.
+    tail: AtomicUsize,
+}
+
+// Producer only moves items in and the consumer only moves them out or looks at items the
+// producer promised not to touch.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn from_hoop<S: Storage<T>>(mut hoop: Hoop<T, S>) -> Shared<T> {
+        let capacity = hoop.capacity();
+        let mut slots = Vec::with_capacity(capacity);
+        while let Some(item) = hoop.pop() {
+            slots.push(UnsafeCell::new(MaybeUninit::new(item)));
+        }
+        let len = slots.len();
+        slots.resize_with(capacity, || UnsafeCell::new(MaybeUninit::uninit()));
+        let one_lap = (capacity + 1).next_power_of_two();
+        Shared {
+            slots: slots.into_boxed_slice(),
+            one_lap,
+            head: AtomicUsize::new(0),
+            released: AtomicUsize::new(0),
+            tail: AtomicUsize::new(if len == capacity && len > 0 { one_lap } else { len }),
+        }
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn advance(&self, position: usize) -> usize {
+        let next = if self.index(position) + 1 < self.capacity() {
+            position + 1
+        } else {
+            (position & !(self.one_lap - 1)).wrapping_add(self.one_lap)
+        };
+        // Top bit is lost when stored in `head`.
+        next & (usize::MAX >> 1)
+    }
+
+    fn retreat(&self, position: usize) -> usize {
+        if self.index(position) > 0 {
+            position - 1
+        } else {
+            let lap = (position & !(self.one_lap - 1)).wrapping_sub(self.one_lap);
+            (lap | (self.capacity() - 1)) & (usize::MAX >> 1)
+        }
+    }
+
+    #[inline]
+    fn index(&self, position: usize) -> usize {
+        position & (self.one_lap - 1)
+    }
+
+    // Number of items between `from` and `to`, which are at most one lap apart.
+    fn distance(&self, from: usize, to: usize) -> usize {
+        if from & !(self.one_lap - 1) == to & !(self.one_lap - 1) {
+            self.index(to) - self.index(from)
+        } else {
+            self.capacity() - self.index(from) + self.index(to)
+        }
+    }
+
+    fn slot(&self, position: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.slots[self.index(position)]
+    }
+
+    fn advance_by(&self, mut position: usize, n: usize) -> usize {
+        for _ in 0..n {
+            position = self.advance(position);
+        }
+        position
+    }
+
+    // `len` slots starting at `position`, which must not wrap around. Loom's cells can't be
+    // viewed as a slice, so grants aren't model-checked.
+    #[cfg(not(loom))]
+    fn slots_from(&self, position: usize, len: usize) -> *mut [MaybeUninit<T>] {
+        let start = self.index(position);
+        debug_assert!(start + len <= self.capacity());
+        let first = self.slots.as_ptr() as *mut MaybeUninit<T>;
+        ptr::slice_from_raw_parts_mut(first.wrapping_add(start), len)
+    }
+
+    // Only called by producer.
+    fn publish(&self, tail: usize, item: T) {
+        self.slot(tail).with_mut(|slot| unsafe {
+            (*slot).write(item);
+        });
+        self.tail.store(self.advance(tail), Ordering::Release);
+    }
+
+    // Called by whichever side claimed `n` items once they are moved out of their slots.
+    fn release(&self, n: usize) {
+        let _ = self
+            .released
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |released| {
+                Some(self.advance_by(released, n))
+            });
+    }
+
+    // Set `PINNED` so that producer can't evict items. Returns position of the oldest item.
+    fn pin(&self) -> usize {
+        let mut head = self.head.load(Ordering::Acquire);
+        while let Err(actual) = self.head.compare_exchange_weak(
+            head,
+            head | PINNED,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            head = actual;
+        }
+        head >> 1
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let mut head = self.head.load(Ordering::Acquire) >> 1;
+        let tail = self.tail.load(Ordering::Acquire);
+        while head != tail {
+            self.slot(head)
+                .with_mut(|slot| unsafe { (*slot).assume_init_drop() });
+            head = self.advance(head);
+        }
+    }
+}
+
+/// Writing half of a single-producer/single-consumer ring.
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Producer<T> {
+    /// Capacity of the ring.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Try writting to a ring.
+    pub fn write(&mut self, item: T) -> WriteResult {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let released = shared.released.load(Ordering::Acquire);
+        if shared.distance(released, tail) == shared.capacity() {
+            return WriteResult::TooMany;
+        }
+        shared.publish(tail, item);
+        WriteResult::Done
+    }
+
+    /// Write even if at a capacity, evicting the oldest item.
+    ///
+    /// Oldest item can't be evicted while [`Consumer::snapshot`] or [`Consumer::read_grant`] is
+    /// alive, in that case `item` is handed back just like with zero capacity.
+    pub fn overwrite(&mut self, item: T) -> Result<(), T> {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        loop {
+            let released = shared.released.load(Ordering::Acquire);
+            if shared.distance(released, tail) < shared.capacity() {
+                shared.publish(tail, item);
+                return Ok(());
+            }
+            let head = shared.head.load(Ordering::Acquire);
+            if head & PINNED == PINNED || shared.capacity() == 0 {
+                return Err(item);
+            }
+            if head >> 1 != released {
+                // Consumer is moving the oldest item out of the very slot we need.
+                spin_loop();
+                continue;
+            }
+            let next = shared.advance(head >> 1) << 1;
+            if shared
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                // Ring is full, so the slot under `tail` is the oldest item and now it's ours.
+                let evicted = shared
+                    .slot(tail)
+                    .with_mut(|slot| unsafe { (*slot).assume_init_read() });
+                shared.publish(tail, item);
+                shared.release(1);
+                drop(evicted);
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(not(loom))]
+impl<T> Producer<T> {
+    /// Grant up to `n` free slots after the newest item to fill in place. Grant is shorter when
+    /// free slots wrap around the end of storage, and empty if ring is full.
+    ///
+    /// ```
+    /// use hoop::Hoop;
+    ///
+    /// let (mut producer, mut consumer) = Hoop::<u8>::with_capacity(8).split();
+    /// let mut grant = producer.write_grant(4);
+    /// grant.buf_init()[..3].copy_from_slice(b"abc");
+    /// grant.commit(3);
+    /// let grant = consumer.read_grant();
+    /// assert_eq!(b"abc", grant.buf());
+    /// grant.release(2);
+    /// assert_eq!(Some(b'c'), consumer.pop());
+    /// ```
+    pub fn write_grant(&mut self, n: usize) -> WriteGrant<'_, T> {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let released = shared.released.load(Ordering::Acquire);
+        let len = n
+            .min(shared.capacity() - shared.distance(released, tail))
+            .min(shared.capacity() - shared.index(tail));
+        WriteGrant {
+            shared,
+            tail,
+            // Consumer never touches free slots.
+            slots: GrantedSlots::new(unsafe { &mut *shared.slots_from(tail, len) }),
+        }
+    }
+}
+
+/// Free slots after the newest item, created by [`Producer::write_grant`]. Works just like
+/// [`hoop::WriteGrant`](../struct.WriteGrant.html), except that committed items become
+/// visible to the consumer.
+#[cfg(not(loom))]
+pub struct WriteGrant<'data, T: 'data> {
+    shared: &'data Shared<T>,
+    tail: usize,
+    slots: GrantedSlots<'data, T>,
+}
+
+#[cfg(not(loom))]
+impl<'data, T: 'data> WriteGrant<'data, T> {
+    /// Number of granted slots.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether no slots were granted.
+    pub fn is_empty(&self) -> bool {
+        self.slots.len() == 0
+    }
+
+    /// Put `item` into `i`-th granted slot, dropping whatever was written there before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of the grant, or if any slot before it is empty.
+    pub fn write(&mut self, i: usize, item: T) {
+        self.slots.write(i, item)
+    }
+
+    /// Copy as many of `items` as fit into slots right after the filled ones. Returns number
+    /// of copied items.
+    pub fn copy_from_slice(&mut self, items: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        self.slots.copy_from_slice(items)
+    }
+
+    /// Granted slots with every one not yet filled set to `T::default()`.
+    pub fn buf_init(&mut self) -> &mut [T]
+    where
+        T: Default,
+    {
+        self.slots.buf_init()
+    }
+
+    /// Publish first `k` slots to the consumer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer than `k` slots are filled.
+    pub fn commit(mut self, k: usize) {
+        self.slots.commit(k);
+        let tail = self.shared.advance_by(self.tail, k);
+        self.shared.tail.store(tail, Ordering::Release);
+    }
+}
+
+/// Reading half of a single-producer/single-consumer ring.
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Consumer<T> {
+    /// Capacity of the ring.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Pop oldest item from a ring.
+    pub fn pop(&mut self) -> Option<T> {
+        let shared = &*self.shared;
+        loop {
+            let head = shared.head.load(Ordering::Acquire);
+            let tail = shared.tail.load(Ordering::Acquire);
+            if head >> 1 == tail {
+                return None;
+            }
+            let next = shared.advance(head >> 1) << 1;
+            if shared
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                let item = shared
+                    .slot(head >> 1)
+                    .with(|slot| unsafe { (*slot).assume_init_read() });
+                shared.release(1);
+                return Some(item);
+            }
+            // Producer evicted the oldest item, try the next one.
+        }
+    }
+
+    /// Pin items written so far to look at them without popping.
+    ///
+    /// Producer can't evict pinned items, so [`Producer::overwrite`] on a full ring fails until
+    /// snapshot is dropped.
+    ///
+    /// ```
+    /// use hoop::Hoop;
+    ///
+    /// let (mut producer, mut consumer) = Hoop::with_capacity(2).split();
+    /// producer.write('1');
+    /// producer.write('2');
+    /// let snapshot = consumer.snapshot();
+    /// let oldest = snapshot.iter().next().unwrap();
+    /// assert_eq!(Err('3'), producer.overwrite('3'));
+    /// assert_eq!(&'1', oldest);
+    /// ```
+    pub fn snapshot(&mut self) -> Snapshot<'_, T> {
+        let shared = &*self.shared;
+        let front = shared.pin();
+        Snapshot {
+            shared,
+            front,
+            back: shared.tail.load(Ordering::Acquire),
+            _items: PhantomData,
+        }
+    }
+
+    /// Grant the oldest items up to the end of storage, to look at in place and then release.
+    ///
+    /// Just like with [`snapshot`](#method.snapshot), producer can't evict items while grant is
+    /// alive.
+    #[cfg(not(loom))]
+    pub fn read_grant(&mut self) -> ReadGrant<'_, T> {
+        let shared = &*self.shared;
+        let head = shared.pin();
+        let tail = shared.tail.load(Ordering::Acquire);
+        let len = shared
+            .distance(head, tail)
+            .min(shared.capacity() - shared.index(head));
+        ReadGrant {
+            shared,
+            head,
+            len,
+            released: 0,
+            _items: PhantomData,
+        }
+    }
+}
+
+/// Items pinned by [`Consumer::snapshot`]. Producer can't evict them until snapshot is dropped.
+///
+/// Items are borrowed from the snapshot, so they can't outlive it:
+///
+/// ```compile_fail
+/// use hoop::Hoop;
+///
+/// let (mut producer, mut consumer) = Hoop::with_capacity(1).split();
+/// producer.write('1');
+/// let oldest = consumer.snapshot().iter().next().unwrap();
+/// producer.overwrite('2');
+/// assert_eq!(&'1', oldest);
+/// ```
+///
+/// Snapshot hands out `&T`, so it can only be shared between threads if `T: Sync`:
+///
+/// ```compile_fail
+/// use hoop::Hoop;
+/// use std::cell::Cell;
+///
+/// fn shared<T: Sync>(_: &T) {}
+/// let (_, mut consumer) = Hoop::<Cell<u8>>::with_capacity(1).split();
+/// shared(&consumer.snapshot());
+/// ```
+pub struct Snapshot<'data, T: 'data> {
+    shared: &'data Shared<T>,
+    front: usize,
+    back: usize,
+    _items: PhantomData<&'data T>,
+}
+
+impl<'data, T: 'data> Snapshot<'data, T> {
+    /// Iterate over pinned items, from the oldest to the newest or the other way around.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            shared: self.shared,
+            front: self.front,
+            back: self.back,
+            _items: PhantomData,
+        }
+    }
+}
+
+impl<'snapshot, 'data, T: 'data> IntoIterator for &'snapshot Snapshot<'data, T> {
+    type Item = &'snapshot T;
+    type IntoIter = Iter<'snapshot, T>;
+
+    fn into_iter(self) -> Iter<'snapshot, T> {
+        self.iter()
+    }
+}
+
+impl<'data, T: 'data> Drop for Snapshot<'data, T> {
+    fn drop(&mut self) {
+        // Nobody else touches `head` while it's pinned.
+        let head = self.shared.head.load(Ordering::Relaxed);
+        self.shared.head.store(head & !PINNED, Ordering::Release);
+    }
+}
+
+/// Iterator created by [`Snapshot::iter`].
+pub struct Iter<'data, T: 'data> {
+    shared: &'data Shared<T>,
+    front: usize,
+    back: usize,
+    _items: PhantomData<&'data T>,
+}
+
+impl<'data, T: 'data> Iterator for Iter<'data, T> {
+    type Item = &'data T;
+    fn next(&mut self) -> Option<&'data T> {
+        if self.front == self.back {
+            return None;
+        }
+        let item = self
+            .shared
+            .slot(self.front)
+            .with(|slot| unsafe { (*slot).assume_init_ref() });
+        self.front = self.shared.advance(self.front);
+        Some(item)
+    }
+}
+
+impl<'data, T: 'data> DoubleEndedIterator for Iter<'data, T> {
+    fn next_back(&mut self) -> Option<&'data T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back = self.shared.retreat(self.back);
+        let item = self
+            .shared
+            .slot(self.back)
+            .with(|slot| unsafe { (*slot).assume_init_ref() });
+        Some(item)
+    }
+}
+
+/// The oldest items that are contiguous in storage, created by [`Consumer::read_grant`].
+#[cfg(not(loom))]
+pub struct ReadGrant<'data, T: 'data> {
+    shared: &'data Shared<T>,
+    head: usize,
+    len: usize,
+    // Leading items to remove once grant is dropped.
+    released: usize,
+    // Hands out `&T` and `&mut T`, so it can only be shared between threads if `T: Sync`.
+    _items: PhantomData<&'data mut T>,
+}
+
+#[cfg(not(loom))]
+impl<'data, T: 'data> ReadGrant<'data, T> {
+    /// Number of granted items.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no items were granted.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Granted items, from the oldest to the newest.
+    pub fn buf(&self) -> &[T] {
+        // Pinned items are initialized and producer leaves them alone.
+        unsafe { &*(self.shared.slots_from(self.head, self.len) as *const [T]) }
+    }
+
+    /// Granted items that can be modified in place.
+    pub fn buf_mut(&mut self) -> &mut [T] {
+        unsafe { &mut *(self.shared.slots_from(self.head, self.len) as *mut [T]) }
+    }
+
+    /// Remove first `k` granted items from a ring, dropping them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is more than granted.
+    pub fn release(mut self, k: usize) {
+        assert!(k <= self.len, "releasing {} items out of {} granted", k, self.len);
+        self.released = k;
+    }
+}
+
+#[cfg(not(loom))]
+impl<'data, T: 'data> Drop for ReadGrant<'data, T> {
+    fn drop(&mut self) {
+        let shared = self.shared;
+        unsafe { ptr::drop_in_place(shared.slots_from(self.head, self.released) as *mut [T]) };
+        // Unpin first, producer waits for `released` to catch up before evicting anything.
+        let head = shared.advance_by(self.head, self.released);
+        shared.head.store(head << 1, Ordering::Release);
+        shared.release(self.released);
+    }
+}
+
+pub(crate) fn split<T, S: Storage<T>>(hoop: Hoop<T, S>) -> (Producer<T>, Consumer<T>) {
+    let shared = Arc::new(Shared::from_hoop(hoop));
+    (
+        Producer {
+            shared: shared.clone(),
+        },
+        Consumer { shared },
+    )
+}
+
+#[cfg(all(test, not(loom)))]
+#[allow(unused_must_use)]
+mod tests {
+    use std::rc::Rc;
+    use std::string::String;
+    use std::thread;
+    use std::vec::Vec;
+
+    use {Hoop, WriteResult};
+
+    #[test]
+    fn write_and_pop() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(2).split();
+        assert_eq!(None, consumer.pop());
+        assert_eq!(WriteResult::Done, producer.write('1'));
+        assert_eq!(WriteResult::Done, producer.write('2'));
+        assert_eq!(WriteResult::TooMany, producer.write('3'));
+        assert_eq!(Some('1'), consumer.pop());
+        assert_eq!(WriteResult::Done, producer.write('3'));
+        assert_eq!(Some('2'), consumer.pop());
+        assert_eq!(Some('3'), consumer.pop());
+        assert_eq!(None, consumer.pop());
+    }
+
+    #[test]
+    fn split_keeps_items() {
+        let mut buffer = Hoop::with_capacity(3);
+        buffer.write('1');
+        buffer.write('2');
+        buffer.write('3');
+        buffer.pop();
+        buffer.write('4');
+        let (mut producer, mut consumer) = buffer.split();
+        assert_eq!(3, producer.capacity());
+        assert_eq!(WriteResult::TooMany, producer.write('5'));
+        let result: Vec<char> = consumer.snapshot().iter().cloned().collect();
+        assert_eq!(vec!['2', '3', '4'], result);
+        assert_eq!(Some('2'), consumer.pop());
+    }
+
+    #[test]
+    fn overwrite_evicts_oldest() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(2).split();
+        producer.write('1');
+        producer.write('2');
+        assert_eq!(Ok(()), producer.overwrite('A'));
+        assert_eq!(Ok(()), producer.overwrite('B'));
+        assert_eq!(Ok(()), producer.overwrite('C'));
+        assert_eq!(Some('B'), consumer.pop());
+        assert_eq!(Some('C'), consumer.pop());
+        assert_eq!(None, consumer.pop());
+    }
+
+    #[test]
+    fn overwrite_fails_while_iterating() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(2).split();
+        producer.write('1');
+        producer.write('2');
+        {
+            let snapshot = consumer.snapshot();
+            let mut iter = snapshot.iter();
+            assert_eq!(Some(&'1'), iter.next());
+            assert_eq!(Err('3'), producer.overwrite('3'));
+            assert_eq!(Some(&'2'), iter.next());
+        }
+        assert_eq!(Ok(()), producer.overwrite('3'));
+        let result: Vec<char> = consumer.snapshot().iter().cloned().collect();
+        assert_eq!(vec!['2', '3'], result);
+    }
+
+    #[test]
+    fn items_outlive_iterator_but_not_snapshot() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(1).split();
+        producer.write(String::from("1"));
+        let snapshot = consumer.snapshot();
+        let oldest = snapshot.iter().next().unwrap();
+        assert_eq!(Err(String::from("2")), producer.overwrite(String::from("2")));
+        // Would be a use after free if eviction went through.
+        assert_eq!("1", oldest);
+        drop(snapshot);
+        assert_eq!(Ok(()), producer.overwrite(String::from("2")));
+        assert_eq!(Some(String::from("2")), consumer.pop());
+    }
+
+    #[test]
+    fn iterator_both_ways() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(4).split();
+        for c in "x1234".chars() {
+            producer.overwrite(c);
+        }
+        consumer.pop();
+        producer.write('5');
+        let snapshot = consumer.snapshot();
+        let mut iter = snapshot.iter();
+        assert_eq!(Some(&'2'), iter.next());
+        assert_eq!(Some(&'5'), iter.next_back());
+        assert_eq!(Some(&'4'), iter.next_back());
+        assert_eq!(Some(&'3'), iter.next());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(0).split();
+        assert_eq!(WriteResult::TooMany, producer.write('1'));
+        assert_eq!(Err('1'), producer.overwrite('1'));
+        assert_eq!(None, consumer.pop());
+    }
+
+    #[test]
+    fn drops_remaining_items() {
+        let token = Rc::new(());
+        {
+            let (mut producer, mut consumer) = Hoop::with_capacity(3).split();
+            for _ in 0..5 {
+                producer.overwrite(token.clone());
+            }
+            consumer.pop();
+            assert_eq!(3, Rc::strong_count(&token));
+            drop(producer);
+            assert_eq!(3, Rc::strong_count(&token));
+        }
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn write_grant_stops_at_wraparound() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(4).split();
+        for i in 0..3 {
+            producer.write(i);
+        }
+        consumer.pop();
+        consumer.pop();
+        let mut grant = producer.write_grant(10);
+        assert_eq!(1, grant.len());
+        grant.write(0, 3);
+        grant.commit(1);
+        let mut grant = producer.write_grant(10);
+        assert_eq!(2, grant.len());
+        assert_eq!(2, grant.copy_from_slice(&[4, 5, 6]));
+        grant.commit(2);
+        assert!(producer.write_grant(1).is_empty());
+        let result: Vec<i32> = consumer.snapshot().iter().cloned().collect();
+        assert_eq!(vec![2, 3, 4, 5], result);
+    }
+
+    #[test]
+    fn read_grant_blocks_eviction() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(3).split();
+        for c in "1234".chars() {
+            producer.overwrite(c);
+        }
+        {
+            let mut grant = consumer.read_grant();
+            assert_eq!(&['2', '3'], grant.buf());
+            assert_eq!(Err('5'), producer.overwrite('5'));
+            grant.buf_mut()[1] = 'c';
+            grant.release(1);
+        }
+        assert_eq!(WriteResult::Done, producer.write('5'));
+        assert_eq!(Ok(()), producer.overwrite('6'));
+        let result: Vec<char> = consumer.snapshot().iter().cloned().collect();
+        assert_eq!(vec!['4', '5', '6'], result);
+        assert!(!consumer.read_grant().is_empty());
+    }
+
+    #[test]
+    fn grants_drop_items_once() {
+        let token = Rc::new(());
+        let (mut producer, mut consumer) = Hoop::with_capacity(4).split();
+        {
+            let mut grant = producer.write_grant(3);
+            for slot in grant.buf_init() {
+                *slot = Some(token.clone());
+            }
+            grant.commit(2);
+        }
+        assert_eq!(3, Rc::strong_count(&token));
+        consumer.read_grant().release(1);
+        assert_eq!(2, Rc::strong_count(&token));
+        consumer.read_grant();
+        assert_eq!(Some(Some(token.clone())), consumer.pop());
+        assert_eq!(1, Rc::strong_count(&token));
+    }
+
+    #[test]
+    fn grants_across_threads() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(16).split();
+        let writer = thread::spawn(move || {
+            let mut next = 0u32;
+            while next < 10_000 {
+                let mut grant = producer.write_grant(5);
+                let len = grant.len();
+                for (i, slot) in grant.buf_init().iter_mut().enumerate() {
+                    *slot = next + i as u32;
+                }
+                grant.commit(len);
+                next += len as u32;
+            }
+        });
+        let mut expected = 0u32;
+        while expected < 10_000 {
+            let grant = consumer.read_grant();
+            for &i in grant.buf() {
+                assert_eq!(expected, i);
+                expected += 1;
+            }
+            let len = grant.len();
+            grant.release(len);
+        }
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn overwrite_across_threads() {
+        let (mut producer, mut consumer) = Hoop::with_capacity(8).split();
+        let writer = thread::spawn(move || {
+            for i in 0..10_000usize {
+                producer.overwrite(i);
+            }
+        });
+        let mut last = None;
+        loop {
+            if let Some(i) = consumer.pop() {
+                assert!(last.is_none_or(|last| i > last));
+                last = Some(i);
+                if i == 9_999 {
+                    break;
+                }
+            }
+        }
+        writer.join().unwrap();
+    }
+}
+
+#[cfg(all(test, loom))]
+#[allow(unused_must_use)]
+mod loom_tests {
+    use loom::thread;
+    use std::vec::Vec;
+
+    use {Hoop, WriteResult};
+
+    #[test]
+    fn write_then_pop() {
+        loom::model(|| {
+            let (mut producer, mut consumer) = Hoop::with_capacity(2).split();
+            let writer = thread::spawn(move || {
+                for i in 0..3 {
+                    while producer.write(i) == WriteResult::TooMany {
+                        thread::yield_now();
+                    }
+                }
+            });
+            let mut expected = 0;
+            while expected < 3 {
+                match consumer.pop() {
+                    Some(i) => {
+                        assert_eq!(expected, i);
+                        expected += 1;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+            writer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn overwrite_races_pop() {
+        loom::model(|| {
+            let (mut producer, mut consumer) = Hoop::with_capacity(1).split();
+            producer.write(0);
+            let writer = thread::spawn(move || {
+                producer.overwrite(1);
+                producer.overwrite(2);
+            });
+            let first = consumer.pop();
+            writer.join().unwrap();
+            let second = consumer.pop();
+            match (first, second) {
+                // `None` when pop lands between eviction and publishing the new item.
+                (Some(0), Some(2)) | (Some(1), Some(2)) | (Some(2), None) | (None, Some(2)) => {}
+                other => panic!("unexpected {:?}", other),
+            }
+        });
+    }
+
+    #[test]
+    fn overwrite_races_iter() {
+        loom::model(|| {
+            let (mut producer, mut consumer) = Hoop::with_capacity(2).split();
+            producer.write(0);
+            producer.write(1);
+            let writer = thread::spawn(move || {
+                producer.overwrite(2);
+            });
+            let seen: Vec<i32> = consumer.snapshot().iter().cloned().collect();
+            // Snapshot may land in the middle of eviction, after the oldest item is claimed and
+            // before the new one is published.
+            assert!(seen == vec![0, 1] || seen == vec![1] || seen == vec![1, 2]);
+            writer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn overwrite_keeps_order() {
+        loom::model(|| {
+            let (mut producer, mut consumer) = Hoop::with_capacity(2).split();
+            let writer = thread::spawn(move || {
+                for i in 0..3 {
+                    producer.overwrite(i);
+                }
+            });
+            let mut seen: Vec<i32> = consumer.pop().into_iter().collect();
+            seen.extend(consumer.pop());
+            writer.join().unwrap();
+            seen.extend(consumer.snapshot().iter().cloned());
+            assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+            assert_eq!(Some(&2), seen.last());
+        });
+    }
+}